@@ -0,0 +1,86 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+use bitboard::pgn::parse_pgn;
+use bitboard::polyglot::BookBuilder;
+
+const USAGE: &str = "usage: book build <games.pgn> <book.bin> [--depth <plies>] [--min-count <games>]";
+
+/// Usage: book build <games.pgn> <book.bin> [options]
+///
+/// Builds a Polyglot book from the games of a PGN file.
+/// Only the first --depth plies (20 by default) of each
+/// game are counted, and moves played in fewer than
+/// --min-count games (1 by default) are left out. Games
+/// with a move that can't be played are skipped.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+fn build(args: &[String]) {
+    let mut paths = vec![];
+    let mut depth = 20;
+    let mut min_count = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = number(args.next()),
+            "--min-count" => min_count = number(args.next()),
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let text = match fs::read_to_string(paths[0]) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("{}: {}", paths[0], error);
+            process::exit(1);
+        }
+    };
+
+    let mut builder = BookBuilder::new(depth, min_count);
+    let games = parse_pgn(&text);
+    let mut skipped = 0;
+
+    for (i, game) in games.iter().enumerate() {
+        if let Err(error) = builder.add_pgn_game(game) {
+            eprintln!("skipping game {}: {}", i + 1, error);
+            skipped += 1;
+        }
+    }
+
+    if let Err(error) = builder.write(paths[1]) {
+        eprintln!("{}: {}", paths[1], error);
+        process::exit(1);
+    }
+
+    println!("Games:   {} ({} skipped)", games.len(), skipped);
+    println!("Entries: {}", builder.entries().len());
+}
+
+/// Returns arg parsed as a number, or exits with the
+/// usage if it's missing or out of range.
+fn number<T: FromStr>(arg: Option<&String>) -> T {
+    match arg.and_then(|arg| arg.parse().ok()) {
+        Some(number) => number,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
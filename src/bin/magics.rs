@@ -0,0 +1,21 @@
+use bitboard::piece::magic::{find_magic, BISHOP_DIRS, ROOK_DIRS};
+use bitboard::rng::Rng;
+use bitboard::square::Square;
+
+/// Searches magic numbers for rooks and bishops on every
+/// square and prints them as Rust constants, ready to be
+/// pasted into src/piece/magic.rs.
+fn main() {
+    let mut rng = Rng::new(0x4D41_4749_4353);
+
+    for (name, dirs) in [("ROOK_MAGICS", &ROOK_DIRS), ("BISHOP_MAGICS", &BISHOP_DIRS)] {
+        println!("pub const {}: [u64; 64] = [", name);
+        for rank in 0..8 {
+            let magics: Vec<String> = (0..8).map(|file| format!("0x{:016X},", find_magic(Square::new(rank * 8 + file), dirs, &mut rng))).collect();
+            println!("    {}", magics[..4].join(" "));
+            println!("    {}", magics[4..].join(" "));
+        }
+        println!("];");
+        println!();
+    }
+}
\ No newline at end of file
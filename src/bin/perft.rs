@@ -0,0 +1,54 @@
+use std::env;
+use std::process;
+use std::time::Instant;
+use bitboard::fen::STARTING_FEN;
+use bitboard::game::Game;
+use bitboard::perft::divide;
+
+/// Usage: perft <fen|startpos> <depth>
+///
+/// Prints the node count below each legal move, like
+/// the "divide" command of other engines, and the total.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.len() < 2 {
+        eprintln!("usage: perft <fen|startpos> <depth>");
+        process::exit(2);
+    }
+
+    let depth: usize = match args[args.len() - 1].parse() {
+        Ok(depth) if depth > 0 => depth,
+        _ => {
+            eprintln!("depth must be a positive number");
+            process::exit(2);
+        }
+    };
+
+    // The FEN may be given as one quoted argument or as
+    // separate words.
+    let fen = args[..args.len() - 1].join(" ");
+    let fen = if fen == "startpos" { STARTING_FEN } else { fen.as_str() };
+
+    let mut game = match Game::from_fen(fen) {
+        Ok(game) => game,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let counts = divide(&mut game, depth);
+    let elapsed = start.elapsed();
+
+    for (m, nodes) in &counts {
+        println!("{}: {}", game.move_to_uci(*m), nodes);
+    }
+
+    let total: u64 = counts.iter().map(|(_, nodes)| nodes).sum();
+    println!();
+    println!("Moves: {}", counts.len());
+    println!("Nodes: {}", total);
+    println!("Time:  {:.3}s", elapsed.as_secs_f64());
+}
\ No newline at end of file
@@ -1,33 +1,86 @@
 use crate::color_side::ColorSide;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
 use super::piece::*;
 use super::square::*;
 use super::color_side::*;
+use super::dir::Dir;
+use super::geometry::*;
+use super::dir::Dir::*;
 
 /// Bitboard type is a 64-bit binary which
 /// each index of it represents following
 /// squares on a chess board.
 ///
+/// Bit s (1 << s) is square s. Squares count from A1
+/// along the ranks (little-endian rank-file mapping),
+/// so A1 is the least significant bit and H8 the most.
+/// Written as a binary literal, the first byte is rank 8
+/// and each byte reads from H to A.
 ///
 /// N | -A -B -C -D -E -F -G -H  | N
 /// --------------------------------
-/// 8 | 56 56 58 59 60 61 62 63 | 8
+/// 8 | 56 57 58 59 60 61 62 63 | 8
 /// 7 | 48 49 50 51 52 53 54 55 | 7
 /// 6 | 40 41 42 43 44 45 46 47 | 6
 /// 5 | 32 33 34 35 36 37 38 39 | 5
 /// 4 | 24 25 26 27 28 29 30 31 | 4
 /// 3 | 16 17 18 19 20 21 22 23 | 3
-/// 2 | 18 09 10 11 12 13 14 15 | 2
-/// 1 | 00 01 02 03 04 05 06 07 | 0
+/// 2 | 08 09 10 11 12 13 14 15 | 2
+/// 1 | 00 01 02 03 04 05 06 07 | 1
 /// --------------------------------
 /// N | -A -B -C -D -E -F -G -H  | N
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(pub u64);
 
-pub type Bitboard = u64;
+const NOT_FILE_A: u64 = !FILE_A.0;
+const NOT_FILE_AB: u64 = !(FILE_A.0 | FILE_B.0);
+const NOT_FILE_H: u64 = !FILE_H.0;
+const NOT_FILE_GH: u64 = !(FILE_G.0 | FILE_H.0);
+
+/// Returns how many bits a square moves by going one
+/// step in dir, and the squares which can go in dir
+/// without wrapping around to the other side of the board.
+const fn dir_offset(dir: Dir) -> (i8, u64) {
+    match dir {
+        Up => (8, !0),
+        Down => (-8, !0),
+        Left => (-1, NOT_FILE_A),
+        Right => (1, NOT_FILE_H),
+        UpLeft => (7, NOT_FILE_A),
+        UpRight => (9, NOT_FILE_H),
+        DownLeft => (-9, NOT_FILE_A),
+        DownRight => (-7, NOT_FILE_H),
+        HorseUpLeft => (15, NOT_FILE_A),
+        HorseUpRight => (17, NOT_FILE_H),
+        HorseRightUp => (10, NOT_FILE_GH),
+        HorseRightDown => (-6, NOT_FILE_GH),
+        HorseDownRight => (-15, NOT_FILE_H),
+        HorseDownLeft => (-17, NOT_FILE_A),
+        HorseLeftDown => (-10, NOT_FILE_AB),
+        HorseLeftUp => (6, NOT_FILE_AB),
+    }
+}
+
+/// Shifts bits of b towards H8 by offset, or towards A1
+/// if offset is negative.
+const fn shift_by(b: u64, offset: i8) -> u64 {
+    if offset > 0 { b << offset } else { b >> -offset }
+}
+
+/// Returns a bitboard with only the bit of given
+/// square set. All the square to bit mapping of the
+/// crate goes through this function.
+pub const fn square_bitboard(s: Square) -> Bitboard {
+    Bitboard(1 << s.index())
+}
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(!0);
 
-/// defines functions to do bitwise operations 
-/// and indexing on bits.
-pub trait Bitboardable {
     // Prints a bitboard.
-    fn draw(&self) {
+    pub fn draw(&self) {
         let mut out = "".to_string();
 
         out.push_str("\n +---+---+---+---+---+---+---+---+\n");
@@ -36,11 +89,10 @@ pub trait Bitboardable {
             for f in 0..=7 {
                 out.push_str(" | ");
 
-                let i = r * 8 + f;
-                if self.at(i) {
-                    out.push_str("X");
+                if self.at(Square::new(r * 8 + f)) {
+                    out.push('X');
                 } else {
-                    out.push_str(" ");
+                    out.push(' ');
                 }
 
             }
@@ -51,19 +103,13 @@ pub trait Bitboardable {
     }
 
     /// Returns a new 64 bit Bitboard with just zeros.
-    fn new() -> Bitboard {
-        0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000 as Bitboard
+    pub fn new() -> Bitboard {
+        Bitboard::EMPTY
     }
     
     /// Returns a bitboard with ones in given indexes.
-    fn from_squares(squares: Vec<Square>) -> Bitboard {
-        let mut bb = Bitboard::new();
-        
-        for i in squares {
-            bb.set(i, true);
-        }
-
-        bb
+    pub fn from_squares(squares: Vec<Square>) -> Bitboard {
+        squares.into_iter().collect()
     }
 
     /// Returns the bit (true or false) which is at 
@@ -72,12 +118,16 @@ pub trait Bitboardable {
     /// # Examples
     ///
     /// ```
-    /// let b: Bitboard = 0b0000000000000000000000000000000000000000000000000000000000000001;
+    /// # use bitboard::bitboard::*;
+    /// # use bitboard::square::*;
+    /// let b = Bitboard(0b0000000000000000000000000000000000000000000000000000000000000001);
     ///
     /// assert_eq!(b.at(F4), false);
-    /// assert_eq!(b.at(H8), true);
+    /// assert_eq!(b.at(A1), true);
     /// ```
-    fn at(&self, s: Square) -> bool;
+    pub fn at(&self, s: Square) -> bool {
+        !(*self & square_bitboard(s)).is_empty()
+    }
 
     /// Sets the value of bit in index square of
     /// self to 'value'.
@@ -86,88 +136,395 @@ pub trait Bitboardable {
     /// # Examples
     ///
     /// ```
-    /// let b: Bitboard = 0b0000000000000000000000000000000000000000000000000000000000000001;
+    /// # use bitboard::bitboard::*;
+    /// # use bitboard::square::*;
+    /// let mut b = Bitboard::new();
     /// 
-    /// b.set(H8, true)
+    /// b.set(H8, true);
     /// assert_eq!(b.at(H8), true);
     /// 
-    /// b.set(H8, false)
+    /// b.set(H8, false);
     /// assert_eq!(b.at(H8), false);
     /// ```
-    fn set(&mut self, square: u8, value: bool);
+    pub fn set(&mut self, square: Square, value: bool) {
+        if value {
+            *self |= square_bitboard(square);
+        } else {
+            *self &= !square_bitboard(square);
+        }
+    }
+
+    /// Same as at, named after set semantics.
+    pub fn contains(&self, square: Square) -> bool {
+        self.at(square)
+    }
+
+    /// Returns the number of set bits (squares).
+    pub fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the lowest square of self, or None
+    /// if self is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::bitboard::*;
+    /// # use bitboard::square::*;
+    /// assert_eq!(Bitboard::from_squares(vec![C3, B7]).lsb(), Some(C3));
+    /// ```
+    pub fn lsb(&self) -> Option<Square> {
+        if self.is_empty() { None } else { Some(Square::new(self.0.trailing_zeros() as u8)) }
+    }
+
+    /// Returns the highest square of self, or None
+    /// if self is empty.
+    pub fn msb(&self) -> Option<Square> {
+        if self.is_empty() { None } else { Some(Square::new(63 - self.0.leading_zeros() as u8)) }
+    }
+
+    /// Moves every square of self one step in dir, the
+    /// set-wise version of Square::get. Squares which
+    /// would fall off the board are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::bitboard::*;
+    /// # use bitboard::square::*;
+    /// # use bitboard::dir::Dir;
+    /// let b = Bitboard::from_squares(vec![A1, H4]);
+    ///
+    /// assert_eq!(b.shift(Dir::Right), Bitboard::from_squares(vec![B1]));
+    /// assert_eq!(b.shift(Dir::HorseUpLeft), Bitboard::from_squares(vec![G6]));
+    /// ```
+    pub fn shift(&self, dir: Dir) -> Bitboard {
+        let (offset, movable) = dir_offset(dir);
+
+        Bitboard(shift_by(self.0 & movable, offset))
+    }
+
+    /// Returns self with every square filled up to
+    /// rank 8.
+    pub fn north_fill(&self) -> Bitboard {
+        let mut b = self.0;
+        b |= b << 8;
+        b |= b << 16;
+        b |= b << 32;
+
+        Bitboard(b)
+    }
+
+    /// Returns self with every square filled down to
+    /// rank 1.
+    pub fn south_fill(&self) -> Bitboard {
+        let mut b = self.0;
+        b |= b >> 8;
+        b |= b >> 16;
+        b |= b >> 32;
+
+        Bitboard(b)
+    }
+
+    /// Returns the files which have at least one square
+    /// of self, filled completely.
+    pub fn file_fill(&self) -> Bitboard {
+        self.north_fill() | self.south_fill()
+    }
+
+    /// Fills self in dir through the squares of empty, using
+    /// Kogge-Stone parallel prefix. The fill stops before the
+    /// first square which is not in empty, so shifting the
+    /// result once more in dir gives sliding attacks of all
+    /// squares of self at once. Only works for the eight
+    /// sliding directions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::bitboard::*;
+    /// # use bitboard::square::*;
+    /// # use bitboard::dir::Dir;
+    /// let rooks = Bitboard::from_squares(vec![A1]);
+    /// let empty = !Bitboard::from_squares(vec![A1, D1]);
+    ///
+    /// assert_eq!(rooks.occluded_fill(Dir::Right, empty), Bitboard::from_squares(vec![A1, B1, C1]));
+    /// ```
+    pub fn occluded_fill(&self, dir: Dir, empty: Bitboard) -> Bitboard {
+        let (offset, _) = dir_offset(dir);
+        assert!(matches!(offset.abs(), 1 | 7 | 8 | 9), "{:?} is not a sliding direction", dir);
+
+        let mut generator = self.0;
+        let mut propagator = empty.0 & Bitboard::FULL.shift(dir).0;
+
+        generator |= propagator & shift_by(generator, offset);
+        propagator &= shift_by(propagator, offset);
+        generator |= propagator & shift_by(generator, 2 * offset);
+        propagator &= shift_by(propagator, 2 * offset);
+        generator |= propagator & shift_by(generator, 4 * offset);
+
+        Bitboard(generator)
+    }
 
     /// Returns bitboard for given piece kind (both colors) 
     /// from initial standard chess position.
-    fn initial_from_piece_kind(kind: PieceKind) -> Bitboard {
-        match kind {      // RANK 1 - RANK 2 - RANK 3 - RANK 4 - RANK 5 - RANK 6 - RANK 7 - RANK 8
+    pub fn initial_from_piece_kind(kind: PieceKind) -> Bitboard {
+        Bitboard(match kind {      // RANK 8 - RANK 7 - RANK 6 - RANK 5 - RANK 4 - RANK 3 - RANK 2 - RANK 1
             PAWN =>   0b00000000_11111111_00000000_00000000_00000000_00000000_11111111_00000000,
-            KING =>   0b00001000_00000000_00000000_00000000_00000000_00000000_00000000_00001000,
+            KING =>   0b00010000_00000000_00000000_00000000_00000000_00000000_00000000_00010000,
             ROOK =>   0b10000001_00000000_00000000_00000000_00000000_00000000_00000000_10000001,
-            QUEEN =>  0b00010000_00000000_00000000_00000000_00000000_00000000_00000000_00010000,
+            QUEEN =>  0b00001000_00000000_00000000_00000000_00000000_00000000_00000000_00001000,
             KNIGHT => 0b01000010_00000000_00000000_00000000_00000000_00000000_00000000_01000010,
             BISHOP => 0b00100100_00000000_00000000_00000000_00000000_00000000_00000000_00100100,
-            _ =>          0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000,
-                          //ABCDEFGH_ABCDEFGH_ABCDEFGH_ABCDEFGH_ABCDEFGH_ABCDEFGH_ABCDEFGH_ABCDEFGH
-        }
+                      //HGFEDCBA_HGFEDCBA_HGFEDCBA_HGFEDCBA_HGFEDCBA_HGFEDCBA_HGFEDCBA_HGFEDCBA
+        })
     }
 
     /// Returns bitboard for given color side (all pieces)
     /// from initial standard chess position.
-    fn initial_from_piece_color(color: ColorSide) -> Bitboard {
-        if color == WHITE {
-            0b11111111_11111111_00000000_00000000_00000000_00000000_00000000_00000000
-        } else if color == BLACK {
-            0b00000000_00000000_00000000_00000000_00000000_00000000_11111111_11111111
-        } else {
-            0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000
+    pub fn initial_from_piece_color(color: ColorSide) -> Bitboard {
+        Bitboard(match color {  // RANK 8 - RANK 7 - RANK 6 - RANK 5 - RANK 4 - RANK 3 - RANK 2 - RANK 1
+            WHITE => 0b00000000_00000000_00000000_00000000_00000000_00000000_11111111_11111111,
+            BLACK => 0b11111111_11111111_00000000_00000000_00000000_00000000_00000000_00000000,
+        })
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Bitboard;
+
+    fn bitxor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 ^ rhs.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Bitboard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Bitboard) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+impl Shl<u32> for Bitboard {
+    type Output = Bitboard;
+
+    fn shl(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 << rhs)
+    }
+}
+
+impl Shr<u32> for Bitboard {
+    type Output = Bitboard;
+
+    fn shr(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 >> rhs)
+    }
+}
+
+/// Iterates over squares of a bitboard from A1 to H8,
+/// popping the lowest set bit each step.
+pub struct BitboardIter(u64);
+
+impl Iterator for BitboardIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
         }
+
+        let square = Square::new(self.0.trailing_zeros() as u8);
+        self.0 &= self.0 - 1;
+
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+
+        (count, Some(count))
     }
 }
 
-impl Bitboardable for Bitboard {
-    fn at(&self, s: Square) -> bool {
-        assert!(s < 64);
+impl ExactSizeIterator for BitboardIter {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = BitboardIter;
 
-        (self >> 63 - s & 1) != 0
+    fn into_iter(self) -> BitboardIter {
+        BitboardIter(self.0)
     }
+}
 
-    fn set(&mut self, square: u8, value: bool) {
-        assert!(square < 64);
-        if value {
-            *self |= 1 << 63 - square;
-        } else {
-            *self &= !(1 << 63 - square);
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(squares: I) -> Bitboard {
+        let mut bb = Bitboard::new();
+
+        for square in squares {
+            bb.set(square, true);
         }
-	}
+
+        bb
+    }
 }
 
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
-    use super::{Bitboard, Bitboardable};
+    use super::Bitboard;
+    use crate::color_side::*;
+    use crate::piece::*;
     use crate::square::*;
+    use crate::dir::Dir::{self, *};
+    use crate::piece::magic::sliding_attacks;
+
+    const DIRS: [Dir; 16] = [
+        Up, Down, Left, Right, UpLeft, UpRight, DownLeft, DownRight,
+        HorseUpLeft, HorseUpRight, HorseRightUp, HorseRightDown,
+        HorseDownRight, HorseDownLeft, HorseLeftDown, HorseLeftUp,
+    ];
 
     #[test]
     fn at_fn() {
-        let b1: Bitboard = 0b1101010111010101110101011101010111010101110101011101010111010101;
-        let b2: Bitboard = 0b1010101110101011101010111010101110101011101010111010101110101011;
-        assert_eq!(b1.at(H1), true);
+        let b1 = Bitboard(0b1010101110101011101010111010101110101011101010111010101110101011);
+        let b2 = Bitboard(0b1101010111010101110101011101010111010101110101011101010111010101);
+        assert_eq!(b1.at(A1), true);
         assert_eq!(b1.at(H1), true);
+        assert_eq!(b1.at(C1), false);
         assert_eq!(b2.at(B1), false);
         assert_eq!(b2.at(G1), true);
+        assert_eq!(b2.at(H8), true);
     }
 
     #[test]
     fn set_fn() {
-        let mut b1: Bitboard = 0b0000000000000000000000000000000000000000000000000000000000000000;
+        let mut b1 = Bitboard(0b0000000000000000000000000000000000000000000000000000000000000000);
         b1.set(A1, true);
         assert_eq!(b1.at(A1), true);
         b1.set(A1, false);
         assert_eq!(b1.at(A1), false);
-        let mut b2: Bitboard = 0b1111111111111111111111111111111111111111111111111111111111111111;
+        let mut b2 = Bitboard(0b1111111111111111111111111111111111111111111111111111111111111111);
         b2.set(H8, true);
         assert_eq!(b2.at(H8), true);
         b2.set(A1, false);
         assert_eq!(b2.at(A1), false);
     }
-}
+
+    #[test]
+    fn bit_is_square() {
+        for s in Square::all() {
+            let mut b = Bitboard::new();
+            b.set(s, true);
+            assert_eq!(b, Bitboard(1 << s.index()));
+        }
+        assert_eq!(Bitboard::initial_from_piece_color(WHITE), Bitboard(0xFFFF));
+        assert_eq!(Bitboard::initial_from_piece_kind(KING), Bitboard((1 << E1.index()) | (1 << E8.index())));
+    }
+
+    #[test]
+    fn operators() {
+        let a = Bitboard::from_squares(vec![A1, C3, H8]);
+        let b = Bitboard::from_squares(vec![C3, D4]);
+
+        assert_eq!(a & b, Bitboard::from_squares(vec![C3]));
+        assert_eq!(a | b, Bitboard::from_squares(vec![A1, C3, D4, H8]));
+        assert_eq!(a ^ b, Bitboard::from_squares(vec![A1, D4, H8]));
+        assert_eq!(!Bitboard::EMPTY, Bitboard::FULL);
+        assert_eq!(b << 8, Bitboard::from_squares(vec![C4, D5]));
+        assert_eq!(b >> 1, Bitboard::from_squares(vec![B3, C4]));
+    }
+
+    #[test]
+    fn set_wise() {
+        let b: Bitboard = [H8, C3, A1].into_iter().collect();
+
+        assert_eq!(b.into_iter().collect::<Vec<_>>(), vec![A1, C3, H8]);
+        assert_eq!(b.into_iter().len(), 3);
+        assert_eq!(b.count(), 3);
+        assert_eq!(b.lsb(), Some(A1));
+        assert_eq!(b.msb(), Some(H8));
+        assert!(b.contains(C3));
+        assert!(!b.contains(C4));
+        assert!(!b.is_empty());
+        assert!(Bitboard::EMPTY.is_empty());
+        assert_eq!(Bitboard::EMPTY.lsb(), None);
+        assert_eq!(Bitboard::EMPTY.msb(), None);
+        assert_eq!(Bitboard::EMPTY.into_iter().next(), None);
+    }
+
+    #[test]
+    fn shift_matches_square_get() {
+        for dir in DIRS {
+            for s in Square::all() {
+                let expected: Bitboard = s.get(&dir).into_iter().collect();
+                assert_eq!(Bitboard::from_squares(vec![s]).shift(dir), expected, "{:?} from {}", dir, s);
+            }
+        }
+        assert_eq!(Bitboard::FULL.shift(Right), !Bitboard(0x0101_0101_0101_0101));
+        assert_eq!(Bitboard::FULL.shift(HorseLeftUp), Bitboard(0x3F3F_3F3F_3F3F_3F00));
+    }
+
+    #[test]
+    fn fills() {
+        let b = Bitboard::from_squares(vec![B3, F6]);
+
+        assert_eq!(b.north_fill(), Bitboard::from_squares(vec![B3, B4, B5, B6, B7, B8, F6, F7, F8]));
+        assert_eq!(b.south_fill(), Bitboard::from_squares(vec![B1, B2, B3, F1, F2, F3, F4, F5, F6]));
+        assert_eq!(b.file_fill(), Bitboard(0x2222_2222_2222_2222));
+        assert_eq!(Bitboard::EMPTY.file_fill(), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn occluded_fill_gives_sliding_attacks() {
+        let occupancy = Bitboard::from_squares(vec![A1, C3, D4, D7, E2, F5, G1, H8, B6]);
+
+        for dir in [Up, Down, Left, Right, UpLeft, UpRight, DownLeft, DownRight] {
+            for s in Square::all() {
+                let attacks = Bitboard::from_squares(vec![s]).occluded_fill(dir, !occupancy).shift(dir);
+                assert_eq!(attacks, sliding_attacks(s, occupancy, &[dir]), "{:?} from {}", dir, s);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+pub type CastlingRights = u8;
+
+pub const NO_CASTLING:     CastlingRights = 0b0000;
+pub const WHITE_KINGSIDE:  CastlingRights = 0b0001;
+pub const WHITE_QUEENSIDE: CastlingRights = 0b0010;
+pub const BLACK_KINGSIDE:  CastlingRights = 0b0100;
+pub const BLACK_QUEENSIDE: CastlingRights = 0b1000;
+pub const ALL_CASTLING:    CastlingRights = 0b1111;
+
+/// Each single castling right, in the order of the FEN
+/// castling field.
+pub const CASTLING_RIGHTS: [CastlingRights; 4] = [WHITE_KINGSIDE, WHITE_QUEENSIDE, BLACK_KINGSIDE, BLACK_QUEENSIDE];
+
+/// Returns index of a single castling right in
+/// CASTLING_RIGHTS.
+pub fn castling_index(right: CastlingRights) -> usize {
+    debug_assert!(right.count_ones() == 1, "{:#06b} is not a single castling right", right);
+
+    right.trailing_zeros() as usize
+}
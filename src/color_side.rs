@@ -1,6 +1,35 @@
 #![allow(dead_code)]
 
-pub type ColorSide = u8;
+/// Side of a piece or of the player to move.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSide {
+    White = 0,
+    Black = 1,
+}
 
-pub const WHITE: ColorSide = 0b1000;
-pub const BLACK: ColorSide = 0b0000;
\ No newline at end of file
+pub const WHITE: ColorSide = ColorSide::White;
+pub const BLACK: ColorSide = ColorSide::Black;
+
+impl ColorSide {
+    pub const ALL: [ColorSide; 2] = [WHITE, BLACK];
+
+    /// Returns the other color side.
+    pub const fn opposite(self) -> ColorSide {
+        match self {
+            WHITE => BLACK,
+            BLACK => WHITE,
+        }
+    }
+
+    /// Returns 0 for white and 1 for black, for indexing
+    /// tables which have an entry per side.
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns an iterator over both sides, white first.
+    pub fn iter() -> impl Iterator<Item = ColorSide> {
+        ColorSide::ALL.into_iter()
+    }
+}
@@ -0,0 +1,492 @@
+use std::fmt::{self, Display};
+use super::castling::*;
+use super::color_side::*;
+use super::game::Game;
+use super::piece::*;
+use super::position::*;
+use super::square::*;
+
+/// FEN of the standard chess starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The six space separated fields of a FEN string,
+/// in the order they appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenField {
+    PiecePlacement,
+    SideToMove,
+    CastlingRights,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+/// Error returned by Game::from_fen. offset is the index
+/// of the offending character in the input string, counted
+/// in characters rather than bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FenError {
+    pub field: FenField,
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FenField::PiecePlacement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::CastlingRights => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} at offset {}: {}", self.field, self.offset, self.message)
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl FenError {
+    fn new(field: FenField, offset: usize, message: &'static str) -> FenError {
+        FenError { field, offset, message }
+    }
+}
+
+impl Game {
+    /// Returns a new Game {} from a FEN string. The halfmove
+    /// clock and fullmove number may be omitted, in which case
+    /// they default to 0 and 1.
+    ///
+    /// Positions which can't come up in a game are rejected:
+    /// the side not to move in check, castling without a rook
+    /// and an en passant square no pawn just skipped over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::fen::STARTING_FEN;
+    /// # use bitboard::game::Game;
+    /// let game = Game::from_fen(STARTING_FEN).unwrap();
+    ///
+    /// assert_eq!(game.to_fen(), STARTING_FEN);
+    /// ```
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        parse_fen(fen).map_err(|error| FenError {
+            offset: fen.char_indices().take_while(|&(i, _)| i < error.offset).count(),
+            ..error
+        })
+    }
+
+    /// Returns FEN string of the game.
+    pub fn to_fen(&self) -> String {
+        let mut out = "".to_string();
+
+        for r in (0..=7).rev() {
+            let mut empty = 0;
+
+            for f in 0..=7 {
+                let square = Square::new(r * 8 + f);
+
+                match self.position.from_square(square) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            out.push_str(empty.to_string().as_str());
+                            empty = 0;
+                        }
+                        out.push(piece.to_fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                out.push_str(empty.to_string().as_str());
+            }
+            if r > 0 {
+                out.push('/');
+            }
+        }
+
+        out.push_str(if self.position.side_to_move() == WHITE { " w " } else { " b " });
+
+        if self.position.castling_rights() == NO_CASTLING {
+            out.push('-');
+        }
+        for (right, c) in [(WHITE_KINGSIDE, 'K'), (WHITE_QUEENSIDE, 'Q'), (BLACK_KINGSIDE, 'k'), (BLACK_QUEENSIDE, 'q')] {
+            if self.position.castling_rights() & right == 0 {
+                continue;
+            }
+
+            // The rook file is written only when K or Q would
+            // be read back as another rook.
+            let rook = self.position.castling_rook(right);
+            let corner = Position::new().castling_rook(right);
+            if outermost_rook(&self.position, right).unwrap_or(corner) == rook {
+                out.push(c);
+            } else {
+                let file = (b'A' + rook.file()) as char;
+                out.push(if c.is_ascii_uppercase() { file } else { file.to_ascii_lowercase() });
+            }
+        }
+
+        match self.position.en_passant() {
+            Some(square) => out.push_str(format!(" {} ", square).as_str()),
+            None => out.push_str(" - "),
+        }
+
+        out.push_str(format!("{} {}", self.halfmove_clock, self.fullmove_number).as_str());
+
+        out
+    }
+}
+
+/// Same as Game::from_fen, except that offsets of the
+/// errors are byte indices into fen.
+fn parse_fen(fen: &str) -> Result<Game, FenError> {
+    let fields = split_fields(fen);
+
+    if fields.len() < 4 {
+        let field = match fields.len() {
+            0 => FenField::PiecePlacement,
+            1 => FenField::SideToMove,
+            2 => FenField::CastlingRights,
+            _ => FenField::EnPassant,
+        };
+        return Err(FenError::new(field, fen.len(), "missing field"));
+    }
+
+    if let Some(&(offset, _)) = fields.get(6) {
+        return Err(FenError::new(FenField::FullmoveNumber, offset, "unexpected trailing field"));
+    }
+
+    let (offset, placement) = fields[0];
+    let mut position = parse_placement(placement, offset)?;
+
+    let (offset, side) = fields[1];
+    let side_to_move = match side {
+        "w" => WHITE,
+        "b" => BLACK,
+        _ => return Err(FenError::new(FenField::SideToMove, offset, "expected 'w' or 'b'")),
+    };
+
+    let (offset, castling) = fields[2];
+    let castling_rights = parse_castling(castling, offset, &mut position)?;
+
+    let (offset, en_passant) = fields[3];
+    let en_passant = parse_en_passant(en_passant, offset, side_to_move, &position)?;
+
+    let halfmove_clock = match fields.get(4) {
+        Some(&(offset, clock)) => parse_number(clock, offset, FenField::HalfmoveClock)?,
+        None => 0,
+    };
+
+    let fullmove_number = match fields.get(5) {
+        Some(&(offset, number)) => {
+            let number = parse_number(number, offset, FenField::FullmoveNumber)?;
+            if number == 0 {
+                return Err(FenError::new(FenField::FullmoveNumber, offset, "must be at least 1"));
+            }
+            number
+        }
+        None => 1,
+    };
+
+    // The side which just moved can't have left its
+    // king in check.
+    let (offset, _) = fields[1];
+    let king = position.from_piece(Piece::new(KING, side_to_move.opposite())).lsb().unwrap();
+    if position.is_square_attacked_by(king, side_to_move) {
+        return Err(FenError::new(FenField::SideToMove, offset, "side not to move is in check"));
+    }
+
+    position.set_side_to_move(side_to_move);
+    position.set_castling_rights(castling_rights);
+    position.set_en_passant(en_passant);
+    position.update_checks();
+
+    Ok(Game {
+        position,
+        played_moves: 0,
+        halfmove_clock,
+        fullmove_number,
+        history: vec![],
+    })
+}
+
+/// Splits a FEN string on whitespace, keeping the offset
+/// of the first character of each field.
+fn split_fields(fen: &str) -> Vec<(usize, &str)> {
+    let mut fields = vec![];
+    let mut start: Option<usize> = None;
+
+    for (i, c) in fen.char_indices() {
+        match (c.is_ascii_whitespace(), start) {
+            (true, Some(s)) => {
+                fields.push((s, &fen[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        fields.push((s, &fen[s..]));
+    }
+
+    fields
+}
+
+fn parse_placement(placement: &str, offset: usize) -> Result<Position, FenError> {
+    let error = |i: usize, message| FenError::new(FenField::PiecePlacement, offset + i, message);
+
+    let mut position = Position::new();
+    let mut rank: u8 = 7;
+    let mut file: u8 = 0;
+
+    for (i, c) in placement.char_indices() {
+        match c {
+            '/' => {
+                if file != 8 {
+                    return Err(error(i, "rank does not have 8 squares"));
+                }
+                if rank == 0 {
+                    return Err(error(i, "more than 8 ranks"));
+                }
+                rank -= 1;
+                file = 0;
+            }
+            '1'..='8' => {
+                file += c as u8 - b'0';
+                if file > 8 {
+                    return Err(error(i, "rank has more than 8 squares"));
+                }
+            }
+            _ => {
+                let piece = Piece::from_fen_char(c).ok_or_else(|| error(i, "unexpected character"))?;
+                if file > 7 {
+                    return Err(error(i, "rank has more than 8 squares"));
+                }
+
+                let square = Square::new(rank * 8 + file);
+                position.put_piece(piece, square);
+                file += 1;
+            }
+        }
+    }
+
+    if rank != 0 || file != 8 {
+        return Err(error(placement.len(), "expected 8 ranks of 8 squares"));
+    }
+
+    for color in [WHITE, BLACK] {
+        if position.from_piece(Piece::new(KING, color)).count() != 1 {
+            return Err(error(0, "each side must have exactly one king"));
+        }
+    }
+
+    Ok(position)
+}
+
+/// Error messages for a castling rook file with no rook
+/// on the back rank.
+const NO_ROOK_ON_FILE: [&str; 8] = [
+    "no rook on file a", "no rook on file b", "no rook on file c", "no rook on file d",
+    "no rook on file e", "no rook on file f", "no rook on file g", "no rook on file h",
+];
+
+/// Parses the castling field and sets the castling rook
+/// squares of position. Besides KQkq, rook files can be
+/// given as letters (Shredder-FEN, for Chess960), like
+/// "HAha", uppercase for white. K and Q stand for the
+/// outermost rook on their side of the king (X-FEN).
+fn parse_castling(castling: &str, offset: usize, position: &mut Position) -> Result<CastlingRights, FenError> {
+    if castling == "-" {
+        return Ok(NO_CASTLING);
+    }
+
+    let mut rights = NO_CASTLING;
+
+    for (i, c) in castling.char_indices() {
+        let error = |message| FenError::new(FenField::CastlingRights, offset + i, message);
+        let color = if c.is_ascii_uppercase() { WHITE } else { BLACK };
+        let (kingside, queenside) = if color == WHITE {
+            (WHITE_KINGSIDE, WHITE_QUEENSIDE)
+        } else {
+            (BLACK_KINGSIDE, BLACK_QUEENSIDE)
+        };
+
+        let (right, rook) = match c {
+            'K' | 'k' => (kingside, outermost_rook(position, kingside).ok_or_else(|| error("no rook on the kingside"))?),
+            'Q' | 'q' => (queenside, outermost_rook(position, queenside).ok_or_else(|| error("no rook on the queenside"))?),
+            'A'..='H' | 'a'..='h' => {
+                let king = position.from_piece(Piece::new(KING, color)).lsb().unwrap();
+                let file = c.to_ascii_lowercase() as u8 - b'a';
+                let rook = Square::from_file_rank(file, if color == WHITE { 0 } else { 7 }).unwrap();
+
+                if position.from_square(rook) != Some(Piece::new(ROOK, color)) {
+                    return Err(error(NO_ROOK_ON_FILE[file as usize]));
+                }
+                if king.rank() != rook.rank() {
+                    return Err(error("castling rook must be on the king's rank"));
+                }
+
+                (if file > king.file() { kingside } else { queenside }, rook)
+            }
+            _ => return Err(error("unexpected character")),
+        };
+
+        if rights & right != 0 {
+            return Err(error("repeated castling right"));
+        }
+        rights |= right;
+        position.set_castling_rook(right, rook);
+    }
+
+    Ok(rights)
+}
+
+/// Returns the rook of the castling right's color which is
+/// farthest from the king on the right's side of the back
+/// rank, if the king is on the back rank.
+fn outermost_rook(position: &Position, right: CastlingRights) -> Option<Square> {
+    let color = if right & (WHITE_KINGSIDE | WHITE_QUEENSIDE) != 0 { WHITE } else { BLACK };
+    let back_rank = if color == WHITE { 0 } else { 7 };
+    let king = position.from_piece(Piece::new(KING, color)).lsb()?;
+    if king.rank() != back_rank {
+        return None;
+    }
+
+    let files: Vec<u8> = if right & (WHITE_KINGSIDE | BLACK_KINGSIDE) != 0 {
+        (king.file() + 1..8).rev().collect()
+    } else {
+        (0..king.file()).collect()
+    };
+
+    files.into_iter()
+        .map(|file| Square::from_file_rank(file, back_rank).unwrap())
+        .find(|&square| position.from_square(square) == Some(Piece::new(ROOK, color)))
+}
+
+/// Parses the en passant square, which must be right
+/// behind a pawn of the side not to move that could have
+/// just made a double push: the square itself and the one
+/// the pawn came from are empty.
+fn parse_en_passant(en_passant: &str, offset: usize, side_to_move: ColorSide, position: &Position) -> Result<Option<Square>, FenError> {
+    if en_passant == "-" {
+        return Ok(None);
+    }
+
+    let square: Square = en_passant.parse()
+        .map_err(|_| FenError::new(FenField::EnPassant, offset, "expected '-' or a square"))?;
+
+    let expected_rank = if side_to_move == WHITE { 5 } else { 2 };
+    if square.rank() != expected_rank {
+        return Err(FenError::new(FenField::EnPassant, offset + 1, "square is on the wrong rank"));
+    }
+
+    let (pawn_rank, from_rank) = if side_to_move == WHITE { (4, 6) } else { (3, 1) };
+    let pawn = Square::from_file_rank(square.file(), pawn_rank).unwrap();
+    let from = Square::from_file_rank(square.file(), from_rank).unwrap();
+
+    if position.from_square(pawn) != Some(Piece::new(PAWN, side_to_move.opposite()))
+        || position.from_square(square).is_some()
+        || position.from_square(from).is_some()
+    {
+        return Err(FenError::new(FenField::EnPassant, offset, "no pawn which just made a double push"));
+    }
+
+    Ok(Some(square))
+}
+
+fn parse_number(number: &str, offset: usize, field: FenField) -> Result<usize, FenError> {
+    if let Some(i) = number.find(|c: char| !c.is_ascii_digit()) {
+        return Err(FenError::new(field, offset + i, "expected a number"));
+    }
+
+    number.parse().map_err(|_| FenError::new(field, offset, "number out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn starting_position() {
+        let game = Game::from_fen(STARTING_FEN).unwrap();
+
+        assert_eq!(game.position, Position::initial());
+        assert_eq!(game.position.side_to_move(), WHITE);
+        assert_eq!(game.position.castling_rights(), ALL_CASTLING);
+        assert_eq!(game.position.en_passant(), None);
+        assert_eq!(game.halfmove_clock, 0);
+        assert_eq!(game.fullmove_number, 1);
+        assert_eq!(Game::default().to_fen(), STARTING_FEN);
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            STARTING_FEN,
+            KIWIPETE,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 17 42",
+            "1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1",
+            "3k4/8/8/8/8/8/8/RK2R2R w E - 0 1",
+        ] {
+            assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn optional_clocks() {
+        let game = Game::from_fen("8/8/8/8/8/8/8/K6k b - -").unwrap();
+
+        assert_eq!(game.position.side_to_move(), BLACK);
+        assert_eq!(game.halfmove_clock, 0);
+        assert_eq!(game.fullmove_number, 1);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |fen| Game::from_fen(fen).err().map(|e| (e.field, e.offset));
+
+        assert_eq!(error("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), Some((FenField::PiecePlacement, 13)));
+        assert_eq!(error("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), Some((FenField::PiecePlacement, 18)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"), Some((FenField::PiecePlacement, 34)));
+        assert_eq!(error("8/8/8/8/8/8/8/8 w - - 0 1"), Some((FenField::PiecePlacement, 0)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"), Some((FenField::SideToMove, 44)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1"), Some((FenField::CastlingRights, 48)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KCkq - 0 1"), Some((FenField::CastlingRights, 47)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KHkq - 0 1"), Some((FenField::CastlingRights, 47)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1"), Some((FenField::EnPassant, 52)));
+        assert_eq!(error("4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1"), Some((FenField::EnPassant, 26)));
+        assert_eq!(error("4k3/4p3/8/4p3/8/8/8/4K3 w - e6 0 1"), Some((FenField::EnPassant, 28)));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w K - 0 1"), Some((FenField::CastlingRights, 22)));
+        assert_eq!(error("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1"), Some((FenField::CastlingRights, 23)));
+        assert_eq!(error("4k2R/8/8/8/8/8/n7/4K3 w - - 0 1"), Some((FenField::SideToMove, 22)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1"), Some((FenField::HalfmoveClock, 53)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0"), Some((FenField::FullmoveNumber, 55)));
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"), Some((FenField::CastlingRights, 45)));
+        assert_eq!(error("8/8/8/8/8/8/8/é w"), Some((FenField::CastlingRights, 17)));
+    }
+
+    #[test]
+    fn castling_file_without_rook() {
+        let error = Game::from_fen("k7/8/8/8/8/8/8/KR6 w A - 0 1").err().unwrap();
+
+        assert_eq!(error.offset, 21);
+        assert_eq!(error.message, "no rook on file a");
+    }
+}
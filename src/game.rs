@@ -1,19 +1,32 @@
 use std::fmt::{self, Display};
 use super::position::*;
-use super::piece::*;
 use super::square::*;
+use super::color_side::*;
+use super::make_move::Undo;
 
+/// Game is a position together with the clocks and the
+/// moves played to reach it. Everything that depends on
+/// whose turn it is should read position.side_to_move(),
+/// since played_moves only counts moves made since the
+/// game was created and says nothing about the color.
+#[derive(Clone)]
 pub struct Game {
     pub position: Position,
     pub played_moves:  usize,
+    pub halfmove_clock: usize,        // moves since last capture or pawn move.
+    pub fullmove_number: usize,       // starts at 1, bumped after black moves.
+    pub history: Vec<Undo>,
 }
 
-impl Game {
+impl Default for Game {
     /// Returns a new Game {} with inital chess board defaults
-    pub fn default() -> Game {
+    fn default() -> Game {
         Game {
             position: Position::initial(),
             played_moves: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: vec![],
         }
     }
 }
@@ -29,7 +42,7 @@ impl Display for Game {
             for f in 0..=7 {
                 out.push_str(" | ");
 
-                let square: Square = r * 8 + f;
+                let square = Square::new(r * 8 + f);
 
                 item = match self.position.from_square(square) {
                     Some(p) => p.to_char(),
@@ -42,7 +55,9 @@ impl Display for Game {
             out.push_str(" |\n +---+---+---+---+---+---+---+---+\n");
         }
 
-        out.push_str(format!(" {} Moves played", self.played_moves).as_str());
+        let side = if self.position.side_to_move() == WHITE { "White" } else { "Black" };
+
+        out.push_str(format!(" {} to move, {} Moves played", side, self.played_moves).as_str());
 
         write!(f, "{}", out)
     }
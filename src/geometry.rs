@@ -0,0 +1,203 @@
+use super::bitboard::*;
+use super::square::*;
+
+pub const RANK_1: Bitboard = Bitboard(0x0000_0000_0000_00FF);
+pub const RANK_2: Bitboard = Bitboard(RANK_1.0 << 8);
+pub const RANK_3: Bitboard = Bitboard(RANK_1.0 << 16);
+pub const RANK_4: Bitboard = Bitboard(RANK_1.0 << 24);
+pub const RANK_5: Bitboard = Bitboard(RANK_1.0 << 32);
+pub const RANK_6: Bitboard = Bitboard(RANK_1.0 << 40);
+pub const RANK_7: Bitboard = Bitboard(RANK_1.0 << 48);
+pub const RANK_8: Bitboard = Bitboard(RANK_1.0 << 56);
+
+pub const FILE_A: Bitboard = Bitboard(0x0101_0101_0101_0101);
+pub const FILE_B: Bitboard = Bitboard(FILE_A.0 << 1);
+pub const FILE_C: Bitboard = Bitboard(FILE_A.0 << 2);
+pub const FILE_D: Bitboard = Bitboard(FILE_A.0 << 3);
+pub const FILE_E: Bitboard = Bitboard(FILE_A.0 << 4);
+pub const FILE_F: Bitboard = Bitboard(FILE_A.0 << 5);
+pub const FILE_G: Bitboard = Bitboard(FILE_A.0 << 6);
+pub const FILE_H: Bitboard = Bitboard(FILE_A.0 << 7);
+
+pub const RANKS: [Bitboard; 8] = [RANK_1, RANK_2, RANK_3, RANK_4, RANK_5, RANK_6, RANK_7, RANK_8];
+pub const FILES: [Bitboard; 8] = [FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H];
+
+/// A1 is a dark square, so is every square whose file
+/// and rank are both even or both odd.
+pub const DARK_SQUARES: Bitboard = Bitboard(0xAA55_AA55_AA55_AA55);
+pub const LIGHT_SQUARES: Bitboard = Bitboard(!DARK_SQUARES.0);
+
+/// D4, E4, D5 and E5.
+pub const CENTER: Bitboard = Bitboard(0x0000_0018_1800_0000);
+pub const EDGES: Bitboard = Bitboard(RANK_1.0 | RANK_8.0 | FILE_A.0 | FILE_H.0);
+
+/// The A1-H8 diagonal and the A8-H1 anti-diagonal.
+pub const MAIN_DIAGONAL: Bitboard = Bitboard(0x8040_2010_0804_0201);
+pub const ANTI_DIAGONAL: Bitboard = Bitboard(0x0102_0408_1020_4080);
+
+static BETWEEN: [[Bitboard; 64]; 64] = geometry_table(false);
+static LINE: [[Bitboard; 64]; 64] = geometry_table(true);
+
+pub fn rank(square: Square) -> Bitboard {
+    RANKS[square.rank() as usize]
+}
+
+pub fn file(square: Square) -> Bitboard {
+    FILES[square.file() as usize]
+}
+
+/// Returns the diagonal (going up to the right) which
+/// square is on.
+pub fn diagonal(square: Square) -> Bitboard {
+    let ranks_up = square.rank() as i8 - square.file() as i8;
+
+    if ranks_up >= 0 { MAIN_DIAGONAL << (8 * ranks_up) as u32 } else { MAIN_DIAGONAL >> (-8 * ranks_up) as u32 }
+}
+
+/// Returns the anti-diagonal (going up to the left)
+/// which square is on.
+pub fn anti_diagonal(square: Square) -> Bitboard {
+    let ranks_up = (square.rank() + square.file()) as i8 - 7;
+
+    if ranks_up >= 0 { ANTI_DIAGONAL << (8 * ranks_up) as u32 } else { ANTI_DIAGONAL >> (-8 * ranks_up) as u32 }
+}
+
+/// Returns squares strictly between a and b if they're
+/// on the same rank, file or diagonal, or an empty
+/// bitboard otherwise.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::bitboard::Bitboard;
+/// # use bitboard::geometry::*;
+/// # use bitboard::square::*;
+/// assert_eq!(between(B2, E5), Bitboard::from_squares(vec![C3, D4]));
+/// assert_eq!(between(B2, C4), Bitboard::EMPTY);
+/// ```
+pub fn between(a: Square, b: Square) -> Bitboard {
+    BETWEEN[a.index()][b.index()]
+}
+
+/// Returns the whole rank, file or diagonal going
+/// through both a and b (edge to edge), or an empty
+/// bitboard if they're not aligned.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::bitboard::Bitboard;
+/// # use bitboard::geometry::*;
+/// # use bitboard::square::*;
+/// assert_eq!(line(B2, E5), MAIN_DIAGONAL);
+/// assert_eq!(line(C4, C4), Bitboard::EMPTY);
+/// ```
+pub fn line(a: Square, b: Square) -> Bitboard {
+    LINE[a.index()][b.index()]
+}
+
+/// Builds the between table, or the line table if full
+/// is set, by walking from each square in the eight
+/// sliding directions.
+const fn geometry_table(full: bool) -> [[Bitboard; 64]; 64] {
+    const DIRS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+    let mut table = [[Bitboard::EMPTY; 64]; 64];
+    let mut from = 0;
+
+    while from < 64 {
+        let mut d = 0;
+        while d < 8 {
+            let (df, dr) = DIRS[d];
+
+            // Whole line through from in this direction,
+            // both ways.
+            let mut whole = square_bitboard(Square::new(from as u8)).0;
+            let (mut f, mut r) = ((from % 8) as i8 - df, (from / 8) as i8 - dr);
+            while 0 <= f && f < 8 && 0 <= r && r < 8 {
+                whole |= square_bitboard(Square::new((r * 8 + f) as u8)).0;
+                f -= df;
+                r -= dr;
+            }
+
+            let mut passed = 0;
+            let (mut f, mut r) = ((from % 8) as i8 + df, (from / 8) as i8 + dr);
+            while 0 <= f && f < 8 && 0 <= r && r < 8 {
+                let to = (r * 8 + f) as usize;
+                whole |= square_bitboard(Square::new(to as u8)).0;
+                table[from][to].0 = passed;
+                passed |= square_bitboard(Square::new(to as u8)).0;
+                f += df;
+                r += dr;
+            }
+
+            if full {
+                let (mut f, mut r) = ((from % 8) as i8 + df, (from / 8) as i8 + dr);
+                while 0 <= f && f < 8 && 0 <= r && r < 8 {
+                    table[from][(r * 8 + f) as usize].0 = whole;
+                    f += df;
+                    r += dr;
+                }
+            }
+
+            d += 1;
+        }
+
+        from += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_side::*;
+    use crate::piece::*;
+
+    #[test]
+    fn masks() {
+        for square in Square::all() {
+            assert!(rank(square).contains(square));
+            assert!(file(square).contains(square));
+            assert_eq!(rank(square).count(), 8);
+            assert_eq!(file(square).count(), 8);
+            assert_eq!(LIGHT_SQUARES.contains(square), (square.file() + square.rank()) % 2 == 1);
+        }
+
+        assert_eq!(FILE_A.file_fill(), FILE_A);
+        assert_eq!(RANK_2, Bitboard::initial_from_piece_kind(PAWN) & Bitboard::initial_from_piece_color(WHITE));
+        assert_eq!(CENTER, Bitboard::from_squares(vec![D4, E4, D5, E5]));
+        assert_eq!(EDGES.count(), 28);
+        assert_eq!(diagonal(C1), Bitboard::from_squares(vec![C1, D2, E3, F4, G5, H6]));
+        assert_eq!(anti_diagonal(C1), Bitboard::from_squares(vec![C1, B2, A3]));
+        assert_eq!(diagonal(H8), MAIN_DIAGONAL);
+        assert_eq!(anti_diagonal(H1), ANTI_DIAGONAL);
+    }
+
+    #[test]
+    fn between_and_line() {
+        assert_eq!(between(A1, A8), FILE_A & !Bitboard::from_squares(vec![A1, A8]));
+        assert_eq!(between(H8, A1), MAIN_DIAGONAL & !Bitboard::from_squares(vec![A1, H8]));
+        assert_eq!(between(E1, H1), Bitboard::from_squares(vec![F1, G1]));
+        assert_eq!(between(E1, F1), Bitboard::EMPTY);
+        assert_eq!(between(B1, C3), Bitboard::EMPTY);
+        assert_eq!(between(D4, D4), Bitboard::EMPTY);
+
+        assert_eq!(line(E1, H1), RANK_1);
+        assert_eq!(line(C3, A1), MAIN_DIAGONAL);
+        assert_eq!(line(G2, H1), ANTI_DIAGONAL);
+        assert_eq!(line(B1, C3), Bitboard::EMPTY);
+
+        for a in Square::all() {
+            for b in Square::all() {
+                assert_eq!(between(a, b), between(b, a));
+                assert_eq!(line(a, b), line(b, a));
+                if !line(a, b).is_empty() {
+                    assert!(line(a, b).contains(a) && line(a, b).contains(b));
+                    assert_eq!(between(a, b) & !line(a, b), Bitboard::EMPTY);
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
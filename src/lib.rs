@@ -0,0 +1,20 @@
+pub mod game;
+pub mod bitboard;
+pub mod geometry;
+pub mod position;
+pub mod piece;
+pub mod square;
+pub mod color_side;
+pub mod dir;
+pub mod castling;
+pub mod fen;
+pub mod moves;
+pub mod movegen;
+pub mod make_move;
+pub mod status;
+pub mod san;
+pub mod perft;
+pub mod pgn;
+pub mod polyglot;
+pub mod rng;
+pub mod zobrist;
\ No newline at end of file
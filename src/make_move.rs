@@ -0,0 +1,262 @@
+use super::castling::*;
+use super::color_side::*;
+use super::game::Game;
+use super::moves::*;
+use super::piece::*;
+use super::square::*;
+
+/// Undo keeps everything make_move can not recover
+/// from the move itself, so unmake_move can step back
+/// to the exact previous state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo {
+    pub played: Move,
+    pub captured: Option<Piece>,
+    pub castling_rights: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: usize,
+    pub hash: u64,                    // of the position before the move,
+                                      // for finding repetitions.
+}
+
+impl Game {
+    /// Plays m, which should be legal (or at least pseudo
+    /// legal) in self, and pushes an Undo record for it
+    /// to history.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let mut game = Game::default();
+    /// game.make_move(Move::new(E2, E4, DOUBLE_PUSH));
+    ///
+    /// assert_eq!(game.position.en_passant(), Some(E3));
+    /// assert_eq!(game.position.side_to_move(), BLACK);
+    /// ```
+    pub fn make_move(&mut self, m: Move) {
+        let (from, to) = (m.from(), m.to());
+        let side = self.position.side_to_move();
+        let piece = self.position.from_square(from).unwrap();
+
+        let captured_square = if m.is_en_passant() { en_passant_victim(m) } else { to };
+        let captured = if m.is_capture() { self.position.from_square(captured_square) } else { None };
+
+        self.history.push(Undo {
+            played: m,
+            captured,
+            castling_rights: self.position.castling_rights(),
+            en_passant: self.position.en_passant(),
+            halfmove_clock: self.halfmove_clock,
+            hash: self.position.hash(),
+        });
+
+        if captured.is_some() {
+            self.position.remove_piece(captured_square);
+        }
+
+        if m.is_castle() {
+            // Both pieces are taken off first, in Chess960
+            // either may land where the other started.
+            let (king_to, rook_to) = m.castling_targets();
+            let king = self.position.remove_piece(from);
+            let rook = self.position.remove_piece(to);
+            self.position.put_piece(king, king_to);
+            self.position.put_piece(rook, rook_to);
+        } else {
+            match m.promotion() {
+                Some(kind) => {
+                    self.position.remove_piece(from);
+                    self.position.put_piece(Piece::new(kind, side), to);
+                }
+                None => {
+                    self.position.move_piece(from, to);
+                }
+            }
+        }
+
+        let rights = self.position.castling_rights() & !self.castling_rights_lost(piece, from, to);
+        self.position.set_castling_rights(rights);
+
+        let en_passant = if m.is_double_push() { Square::from_file_rank(from.file(), (from.rank() + to.rank()) / 2) } else { None };
+        self.position.set_en_passant(en_passant);
+
+        if piece.kind() == PAWN || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if side == BLACK {
+            self.fullmove_number += 1;
+        }
+
+        self.position.set_side_to_move(side.opposite());
+        self.position.update_checks();
+        self.played_moves += 1;
+
+        debug_assert_eq!(self.position.validate(), Ok(()), "after {}", m);
+    }
+
+    /// Takes back the last move played by make_move, and
+    /// returns it. Returns None if there's no move to take
+    /// back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::fen::STARTING_FEN;
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let mut game = Game::default();
+    /// game.make_move(Move::new(G1, F3, QUIET));
+    ///
+    /// assert_eq!(game.unmake_move(), Some(Move::new(G1, F3, QUIET)));
+    /// assert_eq!(game.to_fen(), STARTING_FEN);
+    /// ```
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let undo = self.history.pop()?;
+        let m = undo.played;
+        let (from, to) = (m.from(), m.to());
+        let side = self.position.side_to_move().opposite();
+
+        if m.is_castle() {
+            let (king_to, rook_to) = m.castling_targets();
+            let king = self.position.remove_piece(king_to);
+            let rook = self.position.remove_piece(rook_to);
+            self.position.put_piece(king, from);
+            self.position.put_piece(rook, to);
+        } else if m.is_promotion() {
+            self.position.remove_piece(to);
+            self.position.put_piece(Piece::new(PAWN, side), from);
+        } else {
+            self.position.move_piece(to, from);
+        }
+
+        if let Some(captured) = undo.captured {
+            let captured_square = if m.is_en_passant() { en_passant_victim(m) } else { to };
+            self.position.put_piece(captured, captured_square);
+        }
+
+        self.position.set_castling_rights(undo.castling_rights);
+        self.position.set_en_passant(undo.en_passant);
+        self.halfmove_clock = undo.halfmove_clock;
+
+        if side == BLACK {
+            self.fullmove_number -= 1;
+        }
+
+        self.position.set_side_to_move(side);
+        self.position.update_checks();
+        self.played_moves -= 1;
+
+        debug_assert_eq!(self.position.validate(), Ok(()), "after taking back {}", m);
+        Some(m)
+    }
+
+    /// Returns castling rights which are lost when piece
+    /// moves from a square to another: both rights of its
+    /// color if it's the king, and the right of any castling
+    /// rook which moves or is captured.
+    fn castling_rights_lost(&self, piece: Piece, from: Square, to: Square) -> CastlingRights {
+        let mut lost = NO_CASTLING;
+
+        if piece.kind() == KING {
+            lost |= if piece.color() == WHITE { WHITE_KINGSIDE | WHITE_QUEENSIDE } else { BLACK_KINGSIDE | BLACK_QUEENSIDE };
+        }
+        for right in CASTLING_RIGHTS {
+            let rook = self.position.castling_rook(right);
+            if rook == from || rook == to {
+                lost |= right;
+            }
+        }
+
+        lost
+    }
+}
+
+/// Returns the square of the pawn captured by an en
+/// passant move, which is next to the capturing pawn.
+fn en_passant_victim(m: Move) -> Square {
+    Square::from_file_rank(m.to().file(), m.from().rank()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(fen: &str, uci: &str) -> (Game, String) {
+        let mut game = Game::from_fen(fen).unwrap();
+        let m = game.find_move(uci.parse().unwrap()).unwrap();
+
+        game.make_move(m);
+        assert_eq!(game.position.validate(), Ok(()));
+        let after = game.to_fen();
+
+        assert_eq!(game.unmake_move(), Some(m));
+        assert_eq!(game.to_fen(), fen);
+
+        (game, after)
+    }
+
+    #[test]
+    fn state_updates() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (game, after) = play(fen, "e2e4");
+        assert_eq!(after, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(game.played_moves, 0);
+
+        let (_, after) = play("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", "g8f6");
+        assert_eq!(after, "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2");
+    }
+
+    #[test]
+    fn special_moves() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+        let (_, after) = play(kiwipete, "e1g1");
+        assert_eq!(after, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R4RK1 b kq - 1 1");
+
+        let (_, after) = play(kiwipete, "e1c1");
+        assert_eq!(after, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/2KR3R b kq - 1 1");
+
+        let (_, after) = play(kiwipete, "a1b1");
+        assert_eq!(after, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/1R2K2R b Kkq - 1 1");
+
+        let (_, after) = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "a1a8");
+        assert_eq!(after, "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+
+        let (_, after) = play("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3", "e5d6");
+        assert_eq!(after, "rnbqkbnr/ppp1pppp/3P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+
+        let (_, after) = play("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", "g2h1n");
+        assert_eq!(after, "n1n5/PPPk4/8/8/8/8/4Kp1p/5N1n w - - 0 2");
+
+        let (_, after) = play("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1", "b7c8q");
+        assert_eq!(after, "n1Q5/P1Pk4/8/8/8/8/4Kppp/5N1N b - - 0 1");
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // The king doesn't move, the rook jumps over it.
+        let (_, after) = play("1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1", "g1h1");
+        assert_eq!(after, "1r4kr/8/8/8/8/8/8/1R3RK1 b kq - 1 1");
+
+        // King and rook swap squares.
+        let (_, after) = play("1r4kr/8/8/8/8/8/8/2RK3R w Q - 0 1", "d1c1");
+        assert_eq!(after, "1r4kr/8/8/8/8/8/8/2KR3R b - - 1 1");
+
+        // Capturing a castling rook takes its right away.
+        let (_, after) = play("1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1", "b1b8");
+        assert_eq!(after, "1R4kr/8/8/8/8/8/8/6KR b Kk - 0 1");
+    }
+
+    #[test]
+    fn unmake_empty_history() {
+        assert_eq!(Game::default().unmake_move(), None);
+    }
+}
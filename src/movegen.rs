@@ -0,0 +1,330 @@
+use super::bitboard::*;
+use super::castling::*;
+use super::color_side::*;
+use super::game::Game;
+use super::geometry::*;
+use super::moves::*;
+use super::piece::*;
+use super::piece::piece_attacks::*;
+use super::square::*;
+
+const PROMOTION_KINDS: [PieceKind; 4] = [QUEEN, ROOK, BISHOP, KNIGHT];
+
+impl Game {
+    /// Returns every legal move for the side to move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::game::Game;
+    /// let game = Game::default();
+    ///
+    /// assert_eq!(game.legal_moves().len(), 20);
+    /// ```
+    pub fn legal_moves(&self) -> MoveList {
+        let side = self.position.side_to_move();
+        let king = self.position.from_piece(Piece::new(KING, side));
+        let pinned = self.position.pinned(side);
+        let danger = self.position.king_danger_squares(side);
+        let mut moves = MoveList::new();
+
+        for m in self.pseudo_legal_moves() {
+            // Castling has its own checks, other king moves
+            // only have to stay out of danger.
+            if king.at(m.from()) && !m.is_castle() {
+                if !danger.at(m.to()) {
+                    moves.push(m);
+                }
+                continue;
+            }
+
+            // Out of check, a move of a piece which is not
+            // pinned can't expose the king, except for en
+            // passant which takes a second piece off.
+            let surely_legal = !self.position.is_in_check()
+                && !pinned.at(m.from())
+                && !m.is_en_passant();
+
+            if surely_legal || self.keeps_king_safe(m, king.lsb().unwrap()) {
+                moves.push(m);
+            }
+        }
+
+        moves
+    }
+
+    /// Returns true if m, which is not a king move, leaves
+    /// no enemy piece attacking king. Only the occupancy
+    /// changes for the own king's sake, so it's enough to
+    /// look at attackers through the occupancy after m,
+    /// leaving out the piece m captures.
+    fn keeps_king_safe(&self, m: Move, king: Square) -> bool {
+        let (from, to) = (m.from(), m.to());
+        let enemies = self.position.from_piece_color(self.position.side_to_move().opposite());
+
+        let captured = if m.is_en_passant() {
+            square_bitboard(Square::from_file_rank(to.file(), from.rank()).unwrap())
+        } else {
+            square_bitboard(to)
+        };
+        let occupancy = (self.position.occupancy() & !square_bitboard(from) & !captured) | square_bitboard(to);
+
+        (self.position.attackers_to(king, occupancy) & enemies & !captured).is_empty()
+    }
+
+    /// Returns the legal move with the same squares and
+    /// promotion as m, which is usually parsed from UCI
+    /// notation and so lacks the correct flag. Castling can
+    /// be given either as the king taking its rook or as the
+    /// king moving to its target square.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let game = Game::default();
+    /// let m = game.find_move("e2e4".parse().unwrap());
+    ///
+    /// assert_eq!(m, Some(Move::new(E2, E4, DOUBLE_PUSH)));
+    /// ```
+    pub fn find_move(&self, m: Move) -> Option<Move> {
+        let legal = self.legal_moves();
+        let exact = legal.iter().find(|legal| {
+            legal.from() == m.from() && legal.to() == m.to() && legal.promotion() == m.promotion()
+        });
+
+        // Castling may also be written as the king moving to
+        // its target square, like "e1g1".
+        exact.or_else(|| legal.iter().find(|legal| {
+            legal.is_castle() && legal.from() == m.from() && legal.castling_targets().0 == m.to()
+        })).copied()
+    }
+
+    /// Returns m in UCI notation, with castling written the
+    /// Chess960 way (the king taking its rook) if the
+    /// position needs it, so that every legal move gets a
+    /// different string which find_move reads back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// # fn main() -> Result<(), bitboard::fen::FenError> {
+    /// let game = Game::from_fen("4k3/8/8/8/8/8/8/5K1R w H - 0 1")?;
+    ///
+    /// assert_eq!(game.move_to_uci(Move::new(F1, H1, KING_CASTLE)), "f1h1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn move_to_uci(&self, m: Move) -> String {
+        m.to_uci(self.position.is_chess960())
+    }
+
+    /// Returns moves which follow the movement rules of
+    /// the pieces, but may leave the own king in check.
+    fn pseudo_legal_moves(&self) -> MoveList {
+        let side = self.position.side_to_move();
+        let mut moves = MoveList::new();
+
+        let enemies = self.position.from_piece_color(side.opposite());
+
+        self.pawn_moves(&mut moves);
+
+        for from in self.position.from_piece_color(side) & !self.position.from_piece_kind(PAWN) {
+            let kind = self.position.from_square(from).unwrap().kind();
+
+            for to in side_attacks(kind, &self.position, from, side) {
+                let flag = if enemies.at(to) { CAPTURE } else { QUIET };
+                moves.push(Move::new(from, to, flag));
+            }
+        }
+
+        self.castling_moves(&mut moves);
+
+        moves
+    }
+
+    /// Adds moves of all pawns of the side to move, found
+    /// set-wise: each kind of pawn move is a shift of the
+    /// whole pawn bitboard, and the square a pawn moved from
+    /// is its target minus the shift.
+    fn pawn_moves(&self, moves: &mut MoveList) {
+        let side = self.position.side_to_move();
+        let pawns = self.position.from_piece(Piece::new(PAWN, side));
+        let enemies = self.position.from_piece_color(side.opposite());
+        let empty = !self.position.occupancy();
+
+        // Square offsets of a push and of captures to the
+        // left and to the right.
+        let (forward, left, right) = if side == WHITE { (8, 7, 9) } else { (-8, -9, -7) };
+
+        let mut add = |targets: Bitboard, offset: i8, capture: bool| {
+            let (others, promotions) = split_promotions(side, targets);
+            let from = |to: Square| Square::new((to.index() as i8 - offset) as u8);
+
+            for to in others {
+                moves.push(Move::new(from(to), to, if capture { CAPTURE } else { QUIET }));
+            }
+            for to in promotions {
+                for kind in PROMOTION_KINDS {
+                    moves.push(Move::new_promotion(from(to), to, kind, capture));
+                }
+            }
+        };
+
+        add(pawn_pushes(side, pawns, empty), forward, false);
+        add(pawn_captures_left(side, pawns, enemies), left, true);
+        add(pawn_captures_right(side, pawns, enemies), right, true);
+
+        for to in pawn_double_pushes(side, pawns, empty) {
+            moves.push(Move::new(Square::new((to.index() as i8 - 2 * forward) as u8), to, DOUBLE_PUSH));
+        }
+
+        if let Some(to) = self.position.en_passant() {
+            for from in pawn_en_passant(side, pawns, Some(to)) {
+                moves.push(Move::new(from, to, EN_PASSANT));
+            }
+        }
+    }
+
+    /// Adds castling moves, the king taking its own rook.
+    /// The same rules cover standard chess and Chess960:
+    /// every square the king and rook pass through or land
+    /// on must be empty (but for the two of them), and the
+    /// king must not be in check on any square from where it
+    /// starts to where it lands.
+    fn castling_moves(&self, moves: &mut MoveList) {
+        let side = self.position.side_to_move();
+        let (kingside, queenside) = if side == WHITE {
+            (WHITE_KINGSIDE, WHITE_QUEENSIDE)
+        } else {
+            (BLACK_KINGSIDE, BLACK_QUEENSIDE)
+        };
+
+        let king_from = match self.position.from_piece(Piece::new(KING, side)).lsb() {
+            Some(king) if !self.position.is_in_check() => king,
+            _ => return,
+        };
+
+        let enemies = self.position.from_piece_color(side.opposite());
+        let occupancy = self.position.occupancy();
+
+        for (right, flag) in [(kingside, KING_CASTLE), (queenside, QUEEN_CASTLE)] {
+            let rook_from = self.position.castling_rook(right);
+            if self.position.castling_rights() & right == 0
+                || self.position.from_square(rook_from) != Some(Piece::new(ROOK, side))
+                || rook_from.rank() != king_from.rank()
+            {
+                continue;
+            }
+
+            let m = Move::new(king_from, rook_from, flag);
+            let (king_to, rook_to) = m.castling_targets();
+
+            let king_path = between(king_from, king_to) | square_bitboard(king_to);
+            let rook_path = between(rook_from, rook_to) | square_bitboard(rook_to);
+            let others = occupancy & !square_bitboard(king_from) & !square_bitboard(rook_from);
+            if !((king_path | rook_path) & others).is_empty() {
+                continue;
+            }
+
+            // The rook is left out of the occupancy, since in
+            // Chess960 it may be what shields the king's target
+            // square from a slider along the back rank.
+            let safe = king_path
+                .into_iter()
+                .all(|s| (self.position.attackers_to(s, occupancy ^ square_bitboard(rook_from)) & enemies).is_empty());
+
+            if safe {
+                moves.push(m);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(fen: &str) -> usize {
+        Game::from_fen(fen).unwrap().legal_moves().len()
+    }
+
+    #[test]
+    fn starting_position() {
+        assert_eq!(Game::default().legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn flags() {
+        let game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let moves = game.legal_moves();
+
+        assert!(moves.contains(&Move::new(E1, H1, KING_CASTLE)));
+        assert!(moves.contains(&Move::new(E1, A1, QUEEN_CASTLE)));
+        assert_eq!(game.find_move("e1g1".parse().unwrap()), Some(Move::new(E1, H1, KING_CASTLE)));
+        assert_eq!(game.find_move("e1a1".parse().unwrap()), Some(Move::new(E1, A1, QUEEN_CASTLE)));
+        assert!(moves.contains(&Move::new(A2, A4, DOUBLE_PUSH)));
+        assert!(moves.contains(&Move::new(E5, F7, CAPTURE)));
+        assert!(moves.contains(&Move::new(E5, D3, QUIET)));
+
+        let game = Game::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+        assert_eq!(game.find_move("e5d6".parse().unwrap()), Some(Move::new(E5, D6, EN_PASSANT)));
+        assert_eq!(game.find_move("e5e4".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_eq!(count("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"), 48);
+    }
+
+    #[test]
+    fn en_passant_and_pins() {
+        // The en passant capture would expose the king along the fifth rank.
+        assert_eq!(count("8/8/8/K1pP3r/8/8/8/7k w - c6 0 1"), 5);
+        assert_eq!(count("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"), 14);
+    }
+
+    #[test]
+    fn promotions() {
+        assert_eq!(count("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"), 24);
+    }
+
+    #[test]
+    fn check_evasions() {
+        assert_eq!(count("r6r/1b2k1bq/8/8/7B/8/8/R3K2R b KQ - 3 2"), 8);
+        assert_eq!(count("8/8/8/2k5/2pP4/8/B7/4K3 b - d3 0 3"), 8);
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // The rook on b1 shields c1 from the black rook, but
+        // it moves away when castling.
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/rR1K4 w B - 0 1").unwrap();
+        assert_eq!(game.find_move("d1b1".parse().unwrap()), None);
+
+        // Squares between king and rook must be empty, but
+        // for the king and the rook themselves.
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/1RK1N3 w B - 0 1").unwrap();
+        assert_eq!(game.find_move("c1b1".parse().unwrap()), Some(Move::new(C1, B1, QUEEN_CASTLE)));
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/1R1KN3 w B - 0 1").unwrap();
+        assert_eq!(game.find_move("d1b1".parse().unwrap()), Some(Move::new(D1, B1, QUEEN_CASTLE)));
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/RN1K4 w A - 0 1").unwrap();
+        assert_eq!(game.find_move("d1a1".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn king_steps_back_along_checking_ray() {
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap();
+        assert_eq!(game.find_move("e1f1".parse().unwrap()), None);
+        assert!(game.find_move("e1e2".parse().unwrap()).is_some());
+        assert_eq!(game.legal_moves().len(), 3);
+
+        let game = Game::from_fen("7k/8/8/8/3K4/8/8/b7 w - - 0 1").unwrap();
+        assert_eq!(game.find_move("d4e5".parse().unwrap()), None);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use super::color_side::*;
+use super::piece::*;
+use super::square::*;
+
+/// Move is packed into 16 bits:
+///
+/// bits  0 - 5:  from square
+/// bits  6 - 11: to square
+/// bits 12 - 15: flag
+///
+/// Castling is represented by the king capturing its
+/// own rook, so the same encoding works for Chess960,
+/// where the king may move one square or not at all and
+/// the rook may start next to it. En passant is the pawn
+/// moving to the en passant square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+pub type MoveList = Vec<Move>;
+
+pub type MoveFlag = u8; // third bit marks promotions, second
+                        // bit marks captures.
+
+pub const QUIET:                    MoveFlag = 0b0000; // 0
+pub const DOUBLE_PUSH:              MoveFlag = 0b0001; // 1
+pub const KING_CASTLE:              MoveFlag = 0b0010; // 2
+pub const QUEEN_CASTLE:             MoveFlag = 0b0011; // 3
+pub const CAPTURE:                  MoveFlag = 0b0100; // 4
+pub const EN_PASSANT:               MoveFlag = 0b0101; // 5
+pub const KNIGHT_PROMOTION:         MoveFlag = 0b1000; // 8
+pub const BISHOP_PROMOTION:         MoveFlag = 0b1001; // 9
+pub const ROOK_PROMOTION:           MoveFlag = 0b1010; // 10
+pub const QUEEN_PROMOTION:          MoveFlag = 0b1011; // 11
+pub const KNIGHT_PROMOTION_CAPTURE: MoveFlag = 0b1100; // 12
+pub const BISHOP_PROMOTION_CAPTURE: MoveFlag = 0b1101; // 13
+pub const ROOK_PROMOTION_CAPTURE:   MoveFlag = 0b1110; // 14
+pub const QUEEN_PROMOTION_CAPTURE:  MoveFlag = 0b1111; // 15
+
+impl Move {
+    /// Returns a new Move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let m = Move::new(E2, E4, DOUBLE_PUSH);
+    ///
+    /// assert_eq!(m.to(), E4);
+    /// ```
+    pub fn new(from: Square, to: Square, flag: MoveFlag) -> Move {
+        assert!(flag < 16);
+
+        Move(from.index() as u16 | (to.index() as u16) << 6 | (flag as u16) << 12)
+    }
+
+    /// Returns a new promotion Move to given piece kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::moves::*;
+    /// # use bitboard::piece::*;
+    /// # use bitboard::square::*;
+    /// let m = Move::new_promotion(E7, D8, QUEEN, true);
+    ///
+    /// assert_eq!(m.flag(), QUEEN_PROMOTION_CAPTURE);
+    /// ```
+    pub fn new_promotion(from: Square, to: Square, kind: PieceKind, capture: bool) -> Move {
+        let flag = match kind {
+            KNIGHT => KNIGHT_PROMOTION,
+            BISHOP => BISHOP_PROMOTION,
+            ROOK => ROOK_PROMOTION,
+            QUEEN => QUEEN_PROMOTION,
+            _ => panic!("can not promote to piece kind {:?}", kind),
+        };
+
+        Move::new(from, to, if capture { flag | CAPTURE } else { flag })
+    }
+
+    pub fn from(&self) -> Square {
+        Square::new((self.0 & 0b111111) as u8)
+    }
+
+    pub fn to(&self) -> Square {
+        Square::new(((self.0 >> 6) & 0b111111) as u8)
+    }
+
+    pub fn flag(&self) -> MoveFlag {
+        (self.0 >> 12) as MoveFlag
+    }
+
+    /// Returns true for captures, including en passant
+    /// and capturing promotions.
+    pub fn is_capture(&self) -> bool {
+        self.flag() & CAPTURE != 0
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.flag() & KNIGHT_PROMOTION != 0
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.flag() == EN_PASSANT
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        self.flag() == DOUBLE_PUSH
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flag() == KING_CASTLE || self.flag() == QUEEN_CASTLE
+    }
+
+    /// Returns the squares the king and the rook end up on
+    /// after a castling move: g and f files for kingside,
+    /// c and d files for queenside, in Chess960 too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let m = Move::new(B1, A1, QUEEN_CASTLE);
+    ///
+    /// assert_eq!(m.castling_targets(), (C1, D1));
+    /// ```
+    pub fn castling_targets(&self) -> (Square, Square) {
+        debug_assert!(self.is_castle(), "{:?} is not a castling move", self);
+
+        let rank = self.from().rank();
+        let (king, rook) = if self.flag() == KING_CASTLE { (6, 5) } else { (2, 3) };
+
+        (Square::from_file_rank(king, rank).unwrap(), Square::from_file_rank(rook, rank).unwrap())
+    }
+
+    /// Returns the move in UCI notation. Castling is written
+    /// as the king moving to its target square, or as the
+    /// king taking its rook if chess960 is true, which is
+    /// what UCI expects in Chess960 games.
+    pub fn to_uci(&self, chess960: bool) -> String {
+        let to = if self.is_castle() && !chess960 { self.castling_targets().0 } else { self.to() };
+        let mut uci = format!("{}{}", self.from(), to);
+
+        if let Some(kind) = self.promotion() {
+            uci.push(Piece::new(kind, BLACK).to_fen_char());
+        }
+
+        uci
+    }
+
+    /// Returns the piece kind a pawn is promoted to, or
+    /// None if self is not a promotion.
+    pub fn promotion(&self) -> Option<PieceKind> {
+        if !self.is_promotion() {
+            return None;
+        }
+
+        match self.flag() & 0b0011 {
+            0 => Some(KNIGHT),
+            1 => Some(BISHOP),
+            2 => Some(ROOK),
+            _ => Some(QUEEN),
+        }
+    }
+}
+
+/// Error returned when parsing a move from UCI notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoveError(String);
+
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid move '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+/// Prints the move in UCI long algebraic notation,
+/// like "e2e4" or "e7e8q". Castling is printed as the
+/// king move of standard chess, like "e1g1", use
+/// Game::move_to_uci for Chess960 positions.
+impl Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_uci(false))
+    }
+}
+
+/// Parses a move in UCI long algebraic notation. Only
+/// squares and promotion can be known without a position,
+/// so the flag is either QUIET or a non capturing
+/// promotion. Use Game::find_move to get the legal move
+/// with the correct flag.
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Move, ParseMoveError> {
+        let error = || ParseMoveError(s.to_string());
+
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(error());
+        }
+
+        let from: Square = s[0..2].parse().map_err(|_| error())?;
+        let to: Square = s[2..4].parse().map_err(|_| error())?;
+
+        match s[4..].chars().next() {
+            None => Ok(Move::new(from, to, QUIET)),
+            Some(c) => {
+                let kind = match c {
+                    'n' => KNIGHT,
+                    'b' => BISHOP,
+                    'r' => ROOK,
+                    'q' => QUEEN,
+                    _ => return Err(error()),
+                };
+                Ok(Move::new_promotion(from, to, kind, false))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing() {
+        let m = Move::new(E2, E4, DOUBLE_PUSH);
+        assert_eq!(m.from(), E2);
+        assert_eq!(m.to(), E4);
+        assert_eq!(m.flag(), DOUBLE_PUSH);
+        assert!(m.is_double_push());
+        assert!(!m.is_capture());
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+
+        let m = Move::new(H8, A1, EN_PASSANT);
+        assert_eq!(m.from(), H8);
+        assert_eq!(m.to(), A1);
+        assert!(m.is_capture());
+        assert!(m.is_en_passant());
+    }
+
+    #[test]
+    fn promotions() {
+        for kind in [KNIGHT, BISHOP, ROOK, QUEEN] {
+            let quiet = Move::new_promotion(B7, B8, kind, false);
+            let capture = Move::new_promotion(B7, A8, kind, true);
+
+            assert_eq!(quiet.promotion(), Some(kind));
+            assert_eq!(capture.promotion(), Some(kind));
+            assert!(!quiet.is_capture());
+            assert!(capture.is_capture());
+        }
+        assert_eq!(Move::new(E1, H1, KING_CASTLE).promotion(), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Move::new(E2, E4, DOUBLE_PUSH).to_string(), "e2e4");
+        assert_eq!(Move::new(E1, A1, QUEEN_CASTLE).to_string(), "e1c1");
+        assert_eq!(Move::new(E1, A1, QUEEN_CASTLE).to_uci(true), "e1a1");
+        assert_eq!(Move::new(F8, G8, KING_CASTLE).to_string(), "f8g8");
+        assert_eq!(Move::new(B1, A1, QUEEN_CASTLE).to_string(), "b1c1");
+        assert_eq!(Move::new_promotion(E7, E8, QUEEN, false).to_uci(true), "e7e8q");
+        assert_eq!(Move::new_promotion(E7, E8, QUEEN, false).to_string(), "e7e8q");
+        assert_eq!(Move::new_promotion(A2, B1, KNIGHT, true).to_string(), "a2b1n");
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("e2e4".parse(), Ok(Move::new(E2, E4, QUIET)));
+        assert_eq!("e7e8q".parse(), Ok(Move::new_promotion(E7, E8, QUEEN, false)));
+        assert!("e7e8k".parse::<Move>().is_err());
+        assert!("e2e9".parse::<Move>().is_err());
+        assert!("e2".parse::<Move>().is_err());
+        assert!("e2e4e5".parse::<Move>().is_err());
+    }
+}
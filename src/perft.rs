@@ -0,0 +1,145 @@
+use super::game::Game;
+use super::moves::*;
+
+/// Returns the number of leaf nodes of the legal move
+/// tree of game at given depth. Used to verify move
+/// generation against known node counts.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::game::Game;
+/// # use bitboard::perft::perft;
+/// let mut game = Game::default();
+///
+/// assert_eq!(perft(&mut game, 3), 8902);
+/// ```
+pub fn perft(game: &mut Game, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = game.legal_moves();
+
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for m in moves {
+        game.make_move(m);
+        nodes += perft(game, depth - 1);
+        game.unmake_move();
+    }
+
+    nodes
+}
+
+/// Same as perft, but returns the node count below
+/// each legal move separately.
+pub fn divide(game: &mut Game, depth: usize) -> Vec<(Move, u64)> {
+    assert!(depth > 0);
+
+    let mut counts = vec![];
+    for m in game.legal_moves() {
+        game.make_move(m);
+        counts.push((m, perft(game, depth - 1)));
+        game.unmake_move();
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::STARTING_FEN;
+    use crate::square::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn check(fen: &str, expected: &[u64]) {
+        let mut game = Game::from_fen(fen).unwrap();
+
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&mut game, depth + 1), nodes, "{} at depth {}", fen, depth + 1);
+        }
+        assert_eq!(game.to_fen(), Game::from_fen(fen).unwrap().to_fen());
+    }
+
+    #[test]
+    fn starting_position() {
+        check(STARTING_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        check(KIWIPETE, &[48, 2039, 97862, 4085603]);
+    }
+
+    #[test]
+    fn position_3() {
+        check(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+    }
+
+    #[test]
+    fn position_4() {
+        check(POSITION_4, &[6, 264, 9467, 422333]);
+        check(POSITION_4_MIRRORED, &[6, 264, 9467, 422333]);
+    }
+
+    #[test]
+    fn position_5() {
+        check(POSITION_5, &[44, 1486, 62379, 2103487]);
+    }
+
+    #[test]
+    fn position_6() {
+        check(POSITION_6, &[46, 2079, 89890, 3894594]);
+    }
+
+    #[test]
+    fn chess960() {
+        // Castling rooks on the corners, written with files.
+        check("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w HAha - 0 1", &[48, 2039, 97862]);
+        check("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189, 326672]);
+        check("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002, 667366]);
+        check("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471, 273318]);
+        check("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", &[22, 593, 13440, 382958]);
+        check("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", &[28, 1120, 31058, 1171749]);
+        check("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", &[29, 899, 26578, 824055]);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let mut game = Game::from_fen(KIWIPETE).unwrap();
+        let counts = divide(&mut game, 2);
+
+        assert_eq!(counts.len(), 48);
+        assert_eq!(counts.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+    }
+
+    #[test]
+    fn divide_move_names() {
+        // Castling onto the king's own square, and to the
+        // square a king step goes to.
+        for fen in [KIWIPETE, "1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1", "4k3/8/8/8/8/8/8/5K1R w H - 0 1"] {
+            let mut game = Game::from_fen(fen).unwrap();
+            let moves: Vec<Move> = divide(&mut game, 1).into_iter().map(|(m, _)| m).collect();
+            let names: Vec<String> = moves.iter().map(|&m| game.move_to_uci(m)).collect();
+
+            for (i, name) in names.iter().enumerate() {
+                assert!(!names[..i].contains(name), "{} twice in {}", name, fen);
+                assert_eq!(game.find_move(name.parse().unwrap()), Some(moves[i]));
+            }
+        }
+
+        let game = Game::from_fen(KIWIPETE).unwrap();
+        assert_eq!(game.move_to_uci(Move::new(E1, H1, KING_CASTLE)), "e1g1");
+    }
+}
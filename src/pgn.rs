@@ -0,0 +1,291 @@
+use std::fmt::{self, Display};
+use std::iter::Peekable;
+use std::str::Chars;
+use super::fen::STARTING_FEN;
+use super::game::Game;
+use super::moves::*;
+
+/// Result of a game, as written at the end of its
+/// movetext and in the Result tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    fn from_token(token: &str) -> Option<GameResult> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when the moves of a PGN game can not
+/// be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnError(String);
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// A game read from PGN: its tag pairs, the moves of the
+/// main line in SAN as they were written and the result.
+/// Comments, variations and NAGs are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    fn new() -> PgnGame {
+        PgnGame { tags: vec![], moves: vec![], result: GameResult::Unknown }
+    }
+
+    /// Returns the value of tag name, if the game has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the position the game starts from (the FEN
+    /// tag, or the standard starting position) and its
+    /// moves resolved against it.
+    pub fn replay(&self) -> Result<(Game, Vec<Move>), PgnError> {
+        let fen = self.tag("FEN").unwrap_or(STARTING_FEN);
+        let start = Game::from_fen(fen).map_err(|error| PgnError(format!("FEN tag: {}", error)))?;
+
+        let mut game = start.clone();
+        let mut moves = vec![];
+        for (ply, san) in self.moves.iter().enumerate() {
+            let m = game.parse_san(san).map_err(|error| PgnError(format!("{} at ply {}", error, ply + 1)))?;
+
+            game.make_move(m);
+            moves.push(m);
+        }
+
+        Ok((start, moves))
+    }
+}
+
+/// Reads all games of a PGN file. Parsing is lenient:
+/// anything that isn't a tag, a move number, a result or
+/// skipped text (comments, variations, NAGs and escaped
+/// lines) is taken as a move, and checked only when the
+/// game is replayed.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::pgn::parse_pgn;
+/// let games = parse_pgn("[White \"Morphy\"]\n\n1. e4 e5 2. Nf3 d6 *");
+///
+/// assert_eq!(games[0].moves, vec!["e4", "e5", "Nf3", "d6"]);
+/// ```
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = vec![];
+    let mut game = PgnGame::new();
+    let mut in_movetext = false;
+
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '[' => {
+                if in_movetext {
+                    games.push(std::mem::replace(&mut game, PgnGame::new()));
+                    in_movetext = false;
+                }
+
+                let tag = read_tag(&mut chars);
+                if let Some(pair) = parse_tag(&tag) {
+                    game.tags.push(pair);
+                }
+            }
+            '{' => for _ in chars.by_ref().take_while(|&c| c != '}') {},
+            ';' => {
+                for _ in chars.by_ref().take_while(|&c| c != '\n') {}
+                line_start = true;
+                continue;
+            }
+            '(' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        Some('{') => for _ in chars.by_ref().take_while(|&c| c != '}') {},
+                        Some(_) => (),
+                        None => break,
+                    }
+                }
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut token = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "[]{}();".contains(next) {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+
+                in_movetext = true;
+                if let Some(result) = GameResult::from_token(&token) {
+                    game.result = result;
+                    games.push(std::mem::replace(&mut game, PgnGame::new()));
+                    in_movetext = false;
+                } else if let Some(san) = move_token(&token) {
+                    game.moves.push(san.to_string());
+                }
+            }
+        }
+
+        line_start = c == '\n';
+    }
+
+    if in_movetext || !game.tags.is_empty() {
+        games.push(game);
+    }
+
+    games
+}
+
+/// Reads the inside of a tag pair, up to the closing ']'.
+/// A ']' inside the quoted value, escaped quotes included,
+/// doesn't close the tag.
+fn read_tag(chars: &mut Peekable<Chars>) -> String {
+    let mut tag = String::new();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            ']' if !quoted => break,
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                tag.push(c);
+                if let Some(escaped) = chars.next() {
+                    tag.push(escaped);
+                }
+                continue;
+            }
+            _ => (),
+        }
+        tag.push(c);
+    }
+
+    tag
+}
+
+/// Returns (name, value) of the inside of a tag pair
+/// like `Event "Casual game"`.
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+/// Returns the move in token without its move number, or
+/// None if token has no move (a bare move number, a NAG or
+/// an "e.p." after an en passant capture).
+fn move_token(token: &str) -> Option<&str> {
+    if token.starts_with('$') || token == "e.p." {
+        return None;
+    }
+
+    let san = if token.starts_with(|c: char| c.is_ascii_digit()) && token.contains('.') {
+        token.trim_start_matches(|c: char| c.is_ascii_digit()).trim_start_matches('.')
+    } else {
+        token
+    };
+
+    if san.is_empty() { None } else { Some(san) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::*;
+    use crate::square::*;
+
+    #[test]
+    fn tags_and_movetext() {
+        let text = "\
+[Event \"Casual \\\"blitz\\\"\"]
+[Result \"1-0\"]
+
+1. e4 {best by test} e5 2.Nf3 (2. f4 exf4 (2... d5)) Nc6 $1 3. Bb5 ; Ruy Lopez
+% escaped line 4. a4
+3... a6 1-0
+
+[Event \"Second\"]
+
+1. d4 d5 1/2-1/2
+1. c4 *";
+
+        let games = parse_pgn(text);
+        assert_eq!(games.len(), 3);
+
+        assert_eq!(games[0].tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(games[0].tag("Round"), None);
+        assert_eq!(games[0].moves, vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(games[0].result, GameResult::WhiteWins);
+
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+        assert_eq!(games[1].moves, vec!["d4", "d5"]);
+        assert_eq!(games[1].result, GameResult::Draw);
+
+        assert!(games[2].tags.is_empty());
+        assert_eq!(games[2].result, GameResult::Unknown);
+    }
+
+    #[test]
+    fn brackets_in_tag_values() {
+        let games = parse_pgn("[Event \"Blitz [3+2]\"]\n[Site \"\\\"]\\\" club\"]\n\n1. e4 *");
+
+        assert_eq!(games[0].tag("Event"), Some("Blitz [3+2]"));
+        assert_eq!(games[0].tag("Site"), Some("\"]\" club"));
+        assert_eq!(games[0].moves, vec!["e4"]);
+    }
+
+    #[test]
+    fn replay() {
+        let games = parse_pgn("1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. d4 Nf6 5. Nf3 Bf5 6. Bc4 e6 7. O-O c6 8. Bd2 Nbd7 9. Re1+ Be7 *");
+        let (start, moves) = games[0].replay().unwrap();
+
+        assert_eq!(start.to_fen(), STARTING_FEN);
+        assert_eq!(moves.len(), 18);
+        assert_eq!(moves[12], Move::new(E1, H1, KING_CASTLE));
+        assert_eq!(moves[15], Move::new(B8, D7, QUIET));
+
+        let games = parse_pgn("[FEN \"8/P6k/8/8/8/8/8/K7 w - - 0 1\"]\n\n1. a8=Q Kg6 2. Qb8 *");
+        let (_, moves) = games[0].replay().unwrap();
+        assert_eq!(moves[0], Move::new_promotion(A7, A8, QUEEN, false));
+
+        let games = parse_pgn("1. e4 e5 2. Nf3 Nf6 3. Nc3 Nc6 4. Nd5 Nd4 5. Qd2 *");
+        assert!(games[0].replay().is_err());
+    }
+}
@@ -3,103 +3,212 @@
 use super::color_side::*;
 
 pub mod piece_attacks;
+pub mod attack_tables;
+pub mod magic;
+pub mod sliders;
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+pub mod pext;
 
-pub type PieceKind = u8;
-pub type Piece     = u8; // first bit shows color, other other 3
-                         // bits represent piece kind.
+/// Kind of a piece, regardless of its color.
+///
+/// Same order should be used for a Position array.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    Pawn = 0,
+    King = 1,
+    Rook = 2,
+    Queen = 3,
+    Knight = 4,
+    Bishop = 5,
+}
+
+pub const PAWN:   PieceKind = PieceKind::Pawn;
+pub const KING:   PieceKind = PieceKind::King;
+pub const ROOK:   PieceKind = PieceKind::Rook;
+pub const QUEEN:  PieceKind = PieceKind::Queen;
+pub const KNIGHT: PieceKind = PieceKind::Knight;
+pub const BISHOP: PieceKind = PieceKind::Bishop;
 
-// Same order should be used for a Position array;                         
-pub const PAWN:   PieceKind = 0b0000; // 0
-pub const KING:   PieceKind = 0b0001; // 1
-pub const ROOK:   PieceKind = 0b0010; // 2
-pub const QUEEN:  PieceKind = 0b0011; // 3
-pub const KNIGHT: PieceKind = 0b0100; // 4
-pub const BISHOP: PieceKind = 0b0101; // 5
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [PAWN, KING, ROOK, QUEEN, KNIGHT, BISHOP];
+
+    /// Returns index of the kind (0 to 5), which is also
+    /// its index in a Position array.
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns an iterator over all piece kinds, in the
+    /// order of a Position array.
+    pub fn iter() -> impl Iterator<Item = PieceKind> {
+        PieceKind::ALL.into_iter()
+    }
+}
+
+/// A piece kind with a color. White pieces come first,
+/// each color in the order of PieceKind.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Piece {
+    WhitePawn,
+    WhiteKing,
+    WhiteRook,
+    WhiteQueen,
+    WhiteKnight,
+    WhiteBishop,
+    BlackPawn,
+    BlackKing,
+    BlackRook,
+    BlackQueen,
+    BlackKnight,
+    BlackBishop,
+}
+
+impl Piece {
+    pub const ALL: [Piece; 12] = [
+        Piece::WhitePawn, Piece::WhiteKing, Piece::WhiteRook,
+        Piece::WhiteQueen, Piece::WhiteKnight, Piece::WhiteBishop,
+        Piece::BlackPawn, Piece::BlackKing, Piece::BlackRook,
+        Piece::BlackQueen, Piece::BlackKnight, Piece::BlackBishop,
+    ];
 
-pub trait Pieceable {
     /// Returns a new Piece.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// let p = Piece.new(ROOK, WHITE);
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// let p = Piece::new(ROOK, WHITE);
     /// 
-    /// assert_eq!(p, 0b1010);
+    /// assert_eq!(p, Piece::WhiteRook);
     /// ```
-    fn new(kind: PieceKind, color: ColorSide) -> Piece;
+    pub const fn new(kind: PieceKind, color: ColorSide) -> Piece {
+        Piece::ALL[color.index() * 6 + kind.index()]
+    }
 
-    /// Returns chess symbol for the given piece.
+    /// Returns index of the piece (0 to 11).
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns an iterator over all twelve pieces.
+    pub fn iter() -> impl Iterator<Item = Piece> {
+        Piece::ALL.into_iter()
+    }
+
+    /// Returns the color of the piece.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// let p = Piece.new(QUEEN, BLACK);
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// let p = Piece::new(PAWN, WHITE);
     /// 
-    /// assert_eq!(p.to_char(), '♕');
+    /// assert_eq!(p.color(), WHITE);
     /// ```
-    fn to_char(&self) -> char;
+    pub const fn color(&self) -> ColorSide {
+        ColorSide::ALL[self.index() / 6]
+    }
 
-    /// Returns the color of the piece.
+    /// Returns the kind of the piece.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// let p = Piece.new(PAWN, WHITE);
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// let p = Piece::new(PAWN, WHITE);
     /// 
-    /// assert_eq!(p.color(), WHITE);
+    /// assert_eq!(p.kind(), PAWN);
     /// ```
-    fn color(&self) -> ColorSide;
+    pub const fn kind(&self) -> PieceKind {
+        PieceKind::ALL[self.index() % 6]
+    }
 
-    /// Returns the kind of the piece.
+    /// Returns FEN letter for the given piece, uppercase
+    /// for white and lowercase for black.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// let p = Piece.new(PAWN, WHITE);
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// let p = Piece::new(KNIGHT, BLACK);
     /// 
-    /// assert_eq!(p.kind(), PAWN);
+    /// assert_eq!(p.to_fen_char(), 'n');
     /// ```
-    fn kind(&self) -> PieceKind;
-}
+    pub fn to_fen_char(&self) -> char {
+        let c = match self.kind() {
+            KING => 'k',
+            QUEEN => 'q',
+            ROOK => 'r',
+            BISHOP => 'b',
+            KNIGHT => 'n',
+            PAWN => 'p',
+        };
 
-impl Pieceable for Piece {
-    fn new(kind: PieceKind, color: ColorSide) -> Piece {
-        color | kind
+        if self.color() == WHITE {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
     }
 
-    fn color(&self) -> ColorSide {
-        self & 0b1000
-    }
+    /// Returns the piece for given FEN letter, or None
+    /// if the letter is not one of "PNBRQKpnbrqk".
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// assert_eq!(Piece::from_fen_char('Q'), Some(Piece::new(QUEEN, WHITE)));
+    /// assert_eq!(Piece::from_fen_char('x'), None);
+    /// ```
+    pub fn from_fen_char(c: char) -> Option<Piece> {
+        let kind = match c.to_ascii_lowercase() {
+            'k' => KING,
+            'q' => QUEEN,
+            'r' => ROOK,
+            'b' => BISHOP,
+            'n' => KNIGHT,
+            'p' => PAWN,
+            _ => return None,
+        };
+
+        let color = if c.is_ascii_uppercase() { WHITE } else { BLACK };
 
-    fn kind(&self) -> PieceKind {
-        self & 0b0111
+        Some(Piece::new(kind, color))
     }
 
-    fn to_char(&self) -> char {
-        match self.color() {
-            WHITE => {
-                match self.kind() {
-                    KING => '♚',
-                    QUEEN => '♛',
-                    ROOK => '♜',
-                    BISHOP => '♝',
-                    KNIGHT => '♞',
-                    PAWN => '♟',
-                    _ => ' ',
-                }
-            }
-            BLACK => {
-                match self.kind() {
-                    KING => '♔',
-                    QUEEN => '♕',
-                    ROOK => '♖',
-                    BISHOP => '♗',
-                    KNIGHT => '♘',
-                    PAWN => '♙',
-                    _ => ' ',
-                }
-            }
-            _ => ' ',
+    /// Returns chess symbol for the given piece.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::piece::*;
+    /// let p = Piece::new(QUEEN, BLACK);
+    /// 
+    /// assert_eq!(p.to_char(), '♕');
+    /// ```
+    pub fn to_char(&self) -> char {
+        match self {
+            Piece::WhiteKing => '♚',
+            Piece::WhiteQueen => '♛',
+            Piece::WhiteRook => '♜',
+            Piece::WhiteBishop => '♝',
+            Piece::WhiteKnight => '♞',
+            Piece::WhitePawn => '♟',
+            Piece::BlackKing => '♔',
+            Piece::BlackQueen => '♕',
+            Piece::BlackRook => '♖',
+            Piece::BlackBishop => '♗',
+            Piece::BlackKnight => '♘',
+            Piece::BlackPawn => '♙',
         }
     }
 }
@@ -112,8 +221,8 @@ mod tests {
     fn test_new() {
         let p1 = Piece::new(QUEEN, WHITE);
         let p2 = Piece::new(PAWN, BLACK);
-        assert_eq!(p1, 0b1011);
-        assert_eq!(p2, 0b0000);
+        assert_eq!(p1, Piece::WhiteQueen);
+        assert_eq!(p2, Piece::BlackPawn);
     }
 
     #[test]
@@ -131,4 +240,26 @@ mod tests {
         assert_eq!(p1.kind(), QUEEN);
         assert_eq!(p2.kind(), PAWN);
     }
+
+    #[test]
+    fn test_all() {
+        for piece in Piece::iter() {
+            assert_eq!(Piece::new(piece.kind(), piece.color()), piece);
+        }
+        for (i, kind) in PieceKind::iter().enumerate() {
+            assert_eq!(kind.index(), i);
+        }
+        assert_eq!(WHITE.opposite(), BLACK);
+        assert_eq!(BLACK.opposite(), WHITE);
+        assert_eq!(ColorSide::iter().map(|c| c.index()).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_fen_char() {
+        assert_eq!(Piece::new(KING, WHITE).to_fen_char(), 'K');
+        assert_eq!(Piece::new(PAWN, BLACK).to_fen_char(), 'p');
+        assert_eq!(Piece::from_fen_char('n'), Some(Piece::new(KNIGHT, BLACK)));
+        assert_eq!(Piece::from_fen_char('B'), Some(Piece::new(BISHOP, WHITE)));
+        assert_eq!(Piece::from_fen_char('x'), None);
+    }
 }
\ No newline at end of file
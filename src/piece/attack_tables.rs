@@ -0,0 +1,89 @@
+use super::super::bitboard::*;
+use super::super::color_side::*;
+use super::super::square::*;
+
+/// Attacks of a knight on each square of an empty board.
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&[
+    (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2),
+]);
+
+/// Attacks of a king on each square of an empty board.
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_table(&[
+    (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1),
+]);
+
+/// Capturing squares of a pawn on each square, white
+/// pawns first (index: 0) then black (index: 1).
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_table(&[(-1, 1), (1, 1)]),
+    leaper_table(&[(-1, -1), (1, -1)]),
+];
+
+pub fn knight_attacks(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square.index()]
+}
+
+pub fn king_attacks(square: Square) -> Bitboard {
+    KING_ATTACKS[square.index()]
+}
+
+pub fn pawn_attacks(square: Square, side: ColorSide) -> Bitboard {
+    PAWN_ATTACKS[side.index()][square.index()]
+}
+
+/// Builds a table with a bitboard for each square, which
+/// has the squares at given (file, rank) offsets set
+/// unless they're out of board.
+const fn leaper_table(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::EMPTY; 64];
+    let mut square = 0;
+
+    while square < 64 {
+        let file = (square % 8) as i8;
+        let rank = (square / 8) as i8;
+
+        let mut i = 0;
+        while i < offsets.len() {
+            let (f, r) = (file + offsets[i].0, rank + offsets[i].1);
+            if 0 <= f && f < 8 && 0 <= r && r < 8 {
+                table[square].0 |= square_bitboard(Square::new((r * 8 + f) as u8)).0;
+            }
+            i += 1;
+        }
+
+        square += 1;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::{Dir, Dir::*};
+
+    fn from_dirs(square: Square, dirs: &[Dir]) -> Bitboard {
+        Bitboard::from_squares(dirs.iter().filter_map(|dir| square.get(dir)).collect())
+    }
+
+    #[test]
+    fn tables_match_square_get() {
+        for square in Square::all() {
+            let knight = [HorseUpLeft, HorseUpRight, HorseRightUp, HorseRightDown, HorseDownRight, HorseDownLeft, HorseLeftDown, HorseLeftUp];
+            let king = [Up, Down, Left, Right, UpLeft, UpRight, DownLeft, DownRight];
+
+            assert_eq!(knight_attacks(square), from_dirs(square, &knight));
+            assert_eq!(king_attacks(square), from_dirs(square, &king));
+            assert_eq!(pawn_attacks(square, WHITE), from_dirs(square, &[UpLeft, UpRight]));
+            assert_eq!(pawn_attacks(square, BLACK), from_dirs(square, &[DownLeft, DownRight]));
+        }
+    }
+
+    #[test]
+    fn corners() {
+        assert_eq!(knight_attacks(A1), Bitboard::from_squares(vec![B3, C2]));
+        assert_eq!(king_attacks(H8), Bitboard::from_squares(vec![G8, G7, H7]));
+        assert_eq!(pawn_attacks(A2, WHITE), Bitboard::from_squares(vec![B3]));
+        assert_eq!(pawn_attacks(H7, BLACK), Bitboard::from_squares(vec![G6]));
+    }
+}
\ No newline at end of file
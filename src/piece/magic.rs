@@ -0,0 +1,293 @@
+use std::sync::OnceLock;
+use super::super::bitboard::*;
+use super::super::dir::{Dir, Dir::*};
+use super::super::rng::Rng;
+use super::super::square::*;
+
+// Fancy magic bitboards: the relevant occupancy of a
+// slider (its rays without the last square of each ray)
+// is multiplied by a magic number, and the top bits of
+// the product index a table of precomputed attacks.
+// Magics are found with find_magic and stored below,
+// run `cargo run --release --bin magics` to print new ones.
+
+pub const ROOK_DIRS: [Dir; 4] = [Up, Right, Down, Left];
+pub const BISHOP_DIRS: [Dir; 4] = [UpLeft, UpRight, DownRight, DownLeft];
+
+pub const ROOK_MAGICS: [u64; 64] = [
+    0x108000E290884001, 0x0240200040001004, 0x4100084011022000, 0x0280080210008004,
+    0x0E00040860020030, 0x0200012418109200, 0x0080020001000080, 0x0100004020820100,
+    0x2001800040018020, 0x0040C008601000C0, 0x0000808020001000, 0x2084800800100280,
+    0x0AC1001008010500, 0x2882001002010408, 0x04210001002200A4, 0x0026001100408204,
+    0x2280004000200040, 0x0440042008100020, 0x4800820040201204, 0x0000848008001000,
+    0x0808008004000881, 0x0404008004800200, 0x1008040008100201, 0x0110020000892044,
+    0x4200802080004008, 0x0000500040002000, 0x0420024240100801, 0x6000210100081001,
+    0x00A0040080800800, 0x4001000900028400, 0x4101000100020004, 0x1048004600042881,
+    0x1001214001800080, 0x5282802004804000, 0x0004422086001201, 0x4188080080801000,
+    0x0004008004804800, 0x0440800200800400, 0x8101103204000841, 0x0008010082000054,
+    0x0040400080008020, 0x4000402010004002, 0x4010080024012000, 0x101010400A020020,
+    0x0A88000400828008, 0x4022000408020010, 0x0040081082040001, 0x0005000043890006,
+    0x8000324481020200, 0x0004460820810200, 0x4000100084200480, 0x0809800805100080,
+    0x430B080080040080, 0x0002000810040200, 0x0800610210080400, 0x0248010080440200,
+    0x1020C1110C800021, 0x280100C000205585, 0x1440200008104101, 0x2010100108200501,
+    0x2281000210080005, 0x058200040810215E, 0x0010808102101814, 0x8084240100204082,
+];
+
+pub const BISHOP_MAGICS: [u64; 64] = [
+    0x2440104100410640, 0x01043002020028C0, 0x4241020089012820, 0x0209204200040040,
+    0x5001104000020400, 0x8011100804024000, 0x0001040243400300, 0x0040110088044006,
+    0x000060600A120650, 0x0F42020242440100, 0x4100440454820905, 0x0000080A00214800,
+    0x00810C0308120880, 0x0001120211048020, 0x4030028201A06000, 0x6021003084042001,
+    0x00050052100A0800, 0x0004100801440C00, 0x0212001000220820, 0x0A0C0006061202A0,
+    0x0085001890400431, 0xE002000088044200, 0x2006020109100322, 0x00010080240104A0,
+    0x2110420410440140, 0x0201481021080100, 0x0200300128044040, 0x01900400084010A0,
+    0x2001010040104002, 0x1080802022021000, 0x0044006001082200, 0x100C408000441420,
+    0x0008024122480820, 0x16040108E0210200, 0xB800105000080080, 0x0240040400180120,
+    0x88400020880E0080, 0x0001100100042400, 0x0041010C02011414, 0x0008008888043200,
+    0x0002500405002000, 0x02020114A0000220, 0x04001018040B1804, 0x4000044200880800,
+    0x1010480100408C00, 0x8010501000418020, 0x8414901082200101, 0x3804180183A02100,
+    0x0027819430400040, 0x8380820802020021, 0x8104004404040880, 0x10084010411081A0,
+    0x4002080821010000, 0x0000400214011102, 0x802A10190A040000, 0x90020A8404018601,
+    0x8022004420841010, 0x0000804114100228, 0x0022808447541001, 0x21C0844088208800,
+    0x2005000040A28202, 0x0020004008610440, 0xA001200802080054, 0x0040841400620420,
+];
+
+/// Everything needed to look up attacks of a slider
+/// on a square.
+#[derive(Debug, Clone, Copy)]
+pub struct Magic {
+    pub mask: Bitboard,
+    pub magic: u64,
+    pub shift: u32,
+    pub offset: usize,
+}
+
+impl Magic {
+    /// Returns index of the attacks for given occupancy
+    /// in the attack table.
+    pub fn index(&self, occupancy: Bitboard) -> usize {
+        self.offset + ((occupancy & self.mask).0.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct MagicTables {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    attacks: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+/// Returns rook attacks from square with given occupancy.
+/// The first blocker on each ray is included.
+pub fn rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+
+    tables.attacks[tables.rook[square.index()].index(occupancy)]
+}
+
+/// Returns bishop attacks from square with given occupancy.
+/// The first blocker on each ray is included.
+pub fn bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+
+    tables.attacks[tables.bishop[square.index()].index(occupancy)]
+}
+
+pub fn queen_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
+
+fn tables() -> &'static MagicTables {
+    TABLES.get_or_init(|| {
+        let mut attacks = vec![];
+        let rook = build_magics(&ROOK_DIRS, &ROOK_MAGICS, &mut attacks);
+        let bishop = build_magics(&BISHOP_DIRS, &BISHOP_MAGICS, &mut attacks);
+
+        MagicTables { rook, bishop, attacks }
+    })
+}
+
+/// Appends attack tables of all squares to attacks and
+/// returns the magics pointing into it.
+fn build_magics(dirs: &[Dir], magics: &[u64; 64], attacks: &mut Vec<Bitboard>) -> [Magic; 64] {
+    let mut table = [Magic { mask: Bitboard::EMPTY, magic: 0, shift: 0, offset: 0 }; 64];
+
+    for square in Square::all() {
+        let mask = relevant_occupancy(square, dirs);
+        let magic = Magic {
+            mask,
+            magic: magics[square.index()],
+            shift: 64 - mask.count(),
+            offset: attacks.len(),
+        };
+
+        attacks.resize(attacks.len() + (1 << mask.count()), Bitboard::EMPTY);
+        for occupancy in subsets(mask) {
+            attacks[magic.index(occupancy)] = sliding_attacks(square, occupancy, dirs);
+        }
+
+        table[square.index()] = magic;
+    }
+
+    table
+}
+
+/// Returns squares on the rays of a slider which can
+/// block it, leaving out the last square of each ray
+/// since a piece there can not block anything.
+pub fn relevant_occupancy(square: Square, dirs: &[Dir]) -> Bitboard {
+    let mut mask = Bitboard::new();
+
+    for dir in dirs {
+        let mut s = square;
+        while let Some(target) = s.get(dir) {
+            if target.get(dir).is_none() {
+                break;
+            }
+            mask.set(target, true);
+            s = target;
+        }
+    }
+
+    mask
+}
+
+/// Returns attacks of a slider by walking its rays square
+/// by square, stopping at (and including) the first
+/// occupied square of each ray.
+pub fn sliding_attacks(square: Square, occupancy: Bitboard, dirs: &[Dir]) -> Bitboard {
+    let mut attacks = Bitboard::new();
+
+    for dir in dirs {
+        let mut s = square;
+        while let Some(target) = s.get(dir) {
+            attacks.set(target, true);
+            if occupancy.at(target) {
+                break;
+            }
+            s = target;
+        }
+    }
+
+    attacks
+}
+
+/// Returns all subsets of mask in increasing order, using
+/// the carry rippler trick.
+pub fn subsets(mask: Bitboard) -> Vec<Bitboard> {
+    let mut subsets = vec![];
+    let mut subset = Bitboard::EMPTY;
+
+    loop {
+        subsets.push(subset);
+        subset = Bitboard(subset.0.wrapping_sub(mask.0)) & mask;
+        if subset.is_empty() {
+            break;
+        }
+    }
+
+    subsets
+}
+
+/// Searches for a magic number of a slider moving in dirs
+/// on square, which maps each relevant occupancy to an
+/// index without destructive collisions.
+pub fn find_magic(square: Square, dirs: &[Dir], rng: &mut Rng) -> u64 {
+    let mask = relevant_occupancy(square, dirs);
+    let bits = mask.count();
+    let occupancies = subsets(mask);
+    let attacks: Vec<Bitboard> = occupancies.iter().map(|&o| sliding_attacks(square, o, dirs)).collect();
+
+    let mut used: Vec<Option<Bitboard>> = vec![None; 1 << bits];
+
+    loop {
+        let magic = rng.sparse_u64();
+
+        // Magics which don't spread the mask to the top
+        // bits of the product are unlikely to work.
+        if (mask.0.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        used.iter_mut().for_each(|entry| *entry = None);
+
+        let works = occupancies.iter().zip(&attacks).all(|(&occupancy, &attack)| {
+            let index = (occupancy.0.wrapping_mul(magic) >> (64 - bits)) as usize;
+            match used[index] {
+                None => {
+                    used[index] = Some(attack);
+                    true
+                }
+                Some(existing) => existing == attack,
+            }
+        });
+
+        if works {
+            return magic;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks() {
+        assert_eq!(relevant_occupancy(A1, &ROOK_DIRS).count(), 12);
+        assert_eq!(relevant_occupancy(E4, &ROOK_DIRS).count(), 10);
+        assert_eq!(relevant_occupancy(A1, &BISHOP_DIRS).count(), 6);
+        assert_eq!(relevant_occupancy(E4, &BISHOP_DIRS).count(), 9);
+    }
+
+    fn is_valid_magic(square: Square, dirs: &[Dir], magic: u64) -> bool {
+        let mask = relevant_occupancy(square, dirs);
+        let mut used: Vec<Option<Bitboard>> = vec![None; 1 << mask.count()];
+
+        subsets(mask).into_iter().all(|occupancy| {
+            let index = (occupancy.0.wrapping_mul(magic) >> (64 - mask.count())) as usize;
+            let attacks = sliding_attacks(square, occupancy, dirs);
+            let valid = used[index].is_none_or(|existing| existing == attacks);
+
+            used[index] = Some(attacks);
+            valid
+        })
+    }
+
+    #[test]
+    fn stored_magics() {
+        for square in Square::all() {
+            assert!(is_valid_magic(square, &ROOK_DIRS, ROOK_MAGICS[square.index()]), "rook magic on {}", square);
+            assert!(is_valid_magic(square, &BISHOP_DIRS, BISHOP_MAGICS[square.index()]), "bishop magic on {}", square);
+        }
+    }
+
+    #[test]
+    fn find_magic_works() {
+        let mut rng = Rng::new(7);
+
+        for square in [A1, D4, H7] {
+            assert!(is_valid_magic(square, &ROOK_DIRS, find_magic(square, &ROOK_DIRS, &mut rng)));
+            assert!(is_valid_magic(square, &BISHOP_DIRS, find_magic(square, &BISHOP_DIRS, &mut rng)));
+        }
+    }
+
+    #[test]
+    fn random_occupancies() {
+        let mut rng = Rng::new(0x5EED);
+
+        for _ in 0..2000 {
+            let occupancy = Bitboard(rng.next_u64() & rng.next_u64());
+
+            for square in Square::all() {
+                let rook = sliding_attacks(square, occupancy, &ROOK_DIRS);
+                let bishop = sliding_attacks(square, occupancy, &BISHOP_DIRS);
+
+                assert_eq!(rook_attacks(square, occupancy), rook);
+                assert_eq!(bishop_attacks(square, occupancy), bishop);
+                assert_eq!(queen_attacks(square, occupancy), rook | bishop);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::arch::x86_64::_pext_u64;
+use std::sync::OnceLock;
+use super::super::bitboard::*;
+use super::super::dir::Dir;
+use super::super::square::*;
+use super::magic::{relevant_occupancy, sliding_attacks, subsets, BISHOP_DIRS, ROOK_DIRS};
+
+// PEXT bitboards: _pext_u64 packs the bits of the
+// occupancy which are in the relevant occupancy mask
+// into the low bits of the result, which directly
+// indexes the attack table of the square. No magic
+// numbers are needed, but the CPU must support BMI2.
+
+struct PextEntry {
+    mask: Bitboard,
+    offset: usize,
+}
+
+struct PextTables {
+    rook: Vec<PextEntry>,
+    bishop: Vec<PextEntry>,
+    attacks: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<PextTables> = OnceLock::new();
+
+/// Returns true if the running CPU supports BMI2, so the
+/// functions of this module can be used.
+pub fn is_supported() -> bool {
+    is_x86_feature_detected!("bmi2")
+}
+
+/// Returns rook attacks from square with given occupancy.
+/// Panics if the CPU doesn't support BMI2.
+pub fn rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+
+    lookup(&tables.rook[square.index()], &tables.attacks, occupancy)
+}
+
+/// Returns bishop attacks from square with given occupancy.
+/// Panics if the CPU doesn't support BMI2.
+pub fn bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+
+    lookup(&tables.bishop[square.index()], &tables.attacks, occupancy)
+}
+
+fn lookup(entry: &PextEntry, attacks: &[Bitboard], occupancy: Bitboard) -> Bitboard {
+    assert!(is_supported());
+
+    // SAFETY: BMI2 support is checked above.
+    let index = unsafe { pext(occupancy.0, entry.mask.0) } as usize;
+
+    attacks[entry.offset + index]
+}
+
+#[target_feature(enable = "bmi2")]
+unsafe fn pext(value: u64, mask: u64) -> u64 {
+    _pext_u64(value, mask)
+}
+
+fn tables() -> &'static PextTables {
+    TABLES.get_or_init(|| {
+        let mut attacks = vec![];
+        let rook = build_entries(&ROOK_DIRS, &mut attacks);
+        let bishop = build_entries(&BISHOP_DIRS, &mut attacks);
+
+        PextTables { rook, bishop, attacks }
+    })
+}
+
+/// Appends attack tables of all squares to attacks. pext
+/// of the subsets of a mask, taken in increasing order,
+/// counts up from zero, so no pext is needed to build them.
+fn build_entries(dirs: &[Dir], attacks: &mut Vec<Bitboard>) -> Vec<PextEntry> {
+    Square::all().map(|square| {
+        let mask = relevant_occupancy(square, dirs);
+        let entry = PextEntry { mask, offset: attacks.len() };
+
+        attacks.extend(subsets(mask).into_iter().map(|occupancy| sliding_attacks(square, occupancy, dirs)));
+
+        entry
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::magic;
+    use crate::rng::Rng;
+
+    // Needs BMI2 at compile time, for example with
+    // RUSTFLAGS="-C target-cpu=native".
+    #[test]
+    #[cfg_attr(not(target_feature = "bmi2"), ignore)]
+    fn same_as_magic() {
+        let mut rng = Rng::new(0xB312);
+
+        for _ in 0..10000 {
+            let occupancy = Bitboard(rng.next_u64() & rng.next_u64());
+
+            for square in Square::all() {
+                assert_eq!(rook_attacks(square, occupancy), magic::rook_attacks(square, occupancy));
+                assert_eq!(bishop_attacks(square, occupancy), magic::bishop_attacks(square, occupancy));
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -3,144 +3,220 @@ use super::super::game::Game;
 use super::super::position::*;
 use super::super::square::*;
 use super::super::piece::*;
-use super::super::dir::{Dir, Dir::*};
-use super::super::color_side::*;
+use super::super::dir::Dir;
+use super::super::geometry::*;
+use super::attack_tables;
+use super::sliders;
 
 
 // N | -A -B -C -D -E -F -G -H  | N
 // --------------------------------
-// 8 | 56 56 58 59 60 61 62 63 | 8
+// 8 | 56 57 58 59 60 61 62 63 | 8
 // 7 | 48 49 50 51 52 53 54 55 | 7
 // 6 | 40 41 42 43 44 45 46 47 | 6
 // 5 | 32 33 34 35 36 37 38 39 | 5
 // 4 | 24 25 26 27 28 29 30 31 | 4
 // 3 | 16 17 18 19 20 21 22 23 | 3
-// 2 | 18 09 10 11 12 13 14 15 | 2
-// 1 | 00 01 02 03 04 05 06 07 | 0
+// 2 | 08 09 10 11 12 13 14 15 | 2
+// 1 | 00 01 02 03 04 05 06 07 | 1
 // --------------------------------
 // N | -A -B -C -D -E -F -G -H  | N
 
 /// Generates attacking squares bitboard (captures) for
 /// given piece type in a given game and square. Note that
 /// this is equal to legal moves for all of the pieces, except
-/// for pawns, which have different attacking squares and 
-/// squares they can be pushed to.
-pub fn piece_attacks(piece_kind: PieceKind, game: Game, square: Square) -> Bitboard {
-    let side = if game.played_moves % 2 == 0 { WHITE } else { BLACK };
-
-    return match piece_kind {
-        KING =>   {
-            neighborhood_attacks(&game, square, side, vec![Up, Right, Down, Left, UpLeft, UpRight, DownLeft, DownRight])
-        }
-        ROOK =>   {
-            slider_attacks(&game, square, side, vec![Up, Right, Down, Left])
-        }
-        QUEEN =>  {
-            slider_attacks(&game, square, side, vec![Up, Right, Down, Left, UpLeft, UpRight, DownRight, DownLeft])
-        }
-        BISHOP => {
-            slider_attacks(&game, square, side, vec![UpLeft, UpRight, DownRight, DownLeft])
-        }
-        KNIGHT => knight_attacks(&game, square, side),
-        PAWN =>   pawn_attacks(&game, square, side),
-        _ => Bitboard::new(),
+/// for pawns, which have different attacking squares and
+/// squares they can be pushed to (see pawn_pushes). The piece
+/// is assumed to belong to the side to move.
+pub fn piece_attacks(piece_kind: PieceKind, game: &Game, square: Square) -> Bitboard {
+    side_attacks(piece_kind, &game.position, square, game.position.side_to_move())
+}
+
+/// Same as piece_attacks, but for a piece of the given
+/// side in a bare position.
+pub fn side_attacks(piece_kind: PieceKind, position: &Position, square: Square, side: ColorSide) -> Bitboard {
+    let friends = position.from_piece_color(side);
+    let occupancy = position.occupancy();
+
+    match piece_kind {
+        KING =>   attack_tables::king_attacks(square) & !friends,
+        KNIGHT => attack_tables::knight_attacks(square) & !friends,
+        PAWN =>   attack_tables::pawn_attacks(square, side) & !friends,
+        _ =>      slider_attacks(piece_kind, square, occupancy) & !friends,
     }
 }
 
-fn knight_attacks(game: &Game, s: Square, side: ColorSide) -> Bitboard {
-    let mut legal_squares: Vec<Square> = vec![];
-    for dir in [HorseUpLeft, HorseUpRight, HorseDownRight, HorseDownLeft, HorseLeftDown, HorseLeftUp, HorseRightDown, HorseRightUp] {
-        match s.get(&dir) {
-            Some(target) => {
-                match game.position.from_square(target) {
-                    None => legal_squares.push(target),
-                    Some(piece) => {
-                        if piece.color() != side {
-                            legal_squares.push(target)
-                        }
-                    }
-                }
-            }
-            None => {},
-        }
+/// Returns attacks of a rook, bishop or queen on square,
+/// stopping at (and including) the first occupied square
+/// of each ray, whatever piece is on it.
+pub fn slider_attacks(piece_kind: PieceKind, square: Square, occupancy: Bitboard) -> Bitboard {
+    match piece_kind {
+        ROOK =>   sliders::rook_attacks(square, occupancy),
+        BISHOP => sliders::bishop_attacks(square, occupancy),
+        QUEEN =>  sliders::queen_attacks(square, occupancy),
+        _ => panic!("{:?} is not a slider", piece_kind),
     }
+}
 
-    Bitboard::from_squares(legal_squares)   
+/// Returns squares a slider on square would attack if
+/// the first pieces of blockers on its rays were taken
+/// away, and only those: the squares behind them, up to
+/// and including the next occupied square.
+///
+/// Used to find pins (blockers are own pieces in front of
+/// the king) and discovered attacks.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::bitboard::Bitboard;
+/// # use bitboard::piece::*;
+/// # use bitboard::piece::piece_attacks::xray_attacks;
+/// # use bitboard::square::*;
+/// // Rook on a1, own knight on c1 and enemy king on e1.
+/// let occupancy = Bitboard::from_squares(vec![A1, C1, E1]);
+/// let blockers = Bitboard::from_squares(vec![C1]);
+///
+/// assert_eq!(xray_attacks(ROOK, A1, occupancy, blockers), Bitboard::from_squares(vec![D1, E1]));
+/// ```
+pub fn xray_attacks(piece_kind: PieceKind, square: Square, occupancy: Bitboard, blockers: Bitboard) -> Bitboard {
+    let attacks = slider_attacks(piece_kind, square, occupancy);
+    let blockers = blockers & attacks;
+
+    attacks ^ slider_attacks(piece_kind, square, occupancy ^ blockers)
 }
 
-fn pawn_attacks(game: &Game, s: Square, side: ColorSide) -> Bitboard {
-    if side == WHITE {
-        neighborhood_attacks(game, s, side, vec![UpLeft, UpRight])
-    } else {
-        neighborhood_attacks(game, s, side, vec![DownLeft, DownRight])
-    }
+/// Returns the direction pawns of color are pushed in.
+pub fn pawn_forward(color: ColorSide) -> Dir {
+    if color == WHITE { Dir::Up } else { Dir::Down }
+}
+
+/// Returns squares each of pawns can be pushed to by a
+/// single step. Pawns of color are all moved at once,
+/// empty is the set of empty squares.
+///
+/// # Examples
+///
+/// ```
+/// # use bitboard::bitboard::Bitboard;
+/// # use bitboard::color_side::*;
+/// # use bitboard::piece::piece_attacks::pawn_pushes;
+/// # use bitboard::square::*;
+/// let pawns = Bitboard::from_squares(vec![E2, D7]);
+/// let empty = !Bitboard::from_squares(vec![E2, D7, D8]);
+///
+/// assert_eq!(pawn_pushes(WHITE, pawns, empty), Bitboard::from_squares(vec![E3]));
+/// ```
+pub fn pawn_pushes(color: ColorSide, pawns: Bitboard, empty: Bitboard) -> Bitboard {
+    pawns.shift(pawn_forward(color)) & empty
+}
+
+/// Returns squares pawns on their starting rank (second
+/// for white, seventh for black) can reach with a double
+/// push, both squares in front of them being empty.
+pub fn pawn_double_pushes(color: ColorSide, pawns: Bitboard, empty: Bitboard) -> Bitboard {
+    let start = if color == WHITE { RANK_2 } else { RANK_7 };
+
+    pawn_pushes(color, pawn_pushes(color, pawns & start, empty), empty)
+}
+
+/// Returns squares of targets pawns capture towards file A
+/// (up and left for white, down and left for black).
+pub fn pawn_captures_left(color: ColorSide, pawns: Bitboard, targets: Bitboard) -> Bitboard {
+    pawns.shift(if color == WHITE { Dir::UpLeft } else { Dir::DownLeft }) & targets
+}
+
+/// Returns squares of targets pawns capture towards file H.
+pub fn pawn_captures_right(color: ColorSide, pawns: Bitboard, targets: Bitboard) -> Bitboard {
+    pawns.shift(if color == WHITE { Dir::UpRight } else { Dir::DownRight }) & targets
 }
 
-fn slider_attacks(
-    game: &Game,
-    s: Square,
-    side: ColorSide,
-    dirs: Vec<Dir>,
-) -> Bitboard {
-    let mut legal_squares: Vec<Square> = vec![];
-    let mut square = s;
-    for dir in dirs {
-        loop {
-            match square.get(&dir) {
-                Some(target) => {
-                    match game.position.from_square(target) {
-                        None => {
-                            legal_squares.push(target);
-                            square = target;
-                        }
-                        Some(piece) => {
-                            if piece.color() != side {
-                                legal_squares.push(target);
-                                // TODO: explain why
-                                if piece.kind() != KING {
-                                    break;
-                                }
-                                square = target;
-                            } else {
-                                break;
-                            }
-                        }
-                    }
-                }
-                None => {
-                    break;
-                }
-            }
-        }
-        square = s;
+/// Returns pawns which can capture en passant on the en
+/// passant square, if there's one.
+pub fn pawn_en_passant(color: ColorSide, pawns: Bitboard, en_passant: Option<Square>) -> Bitboard {
+    match en_passant {
+        // A pawn of color captures on the square from the
+        // squares a pawn of the other color there attacks.
+        Some(square) => attack_tables::pawn_attacks(square, color.opposite()) & pawns,
+        None => Bitboard::EMPTY,
     }
+}
 
-    Bitboard::from_squares(legal_squares)
+/// Splits target squares of pawns of color into ones on
+/// their last rank, which are promotions, and the rest.
+/// Returns (others, promotions).
+pub fn split_promotions(color: ColorSide, targets: Bitboard) -> (Bitboard, Bitboard) {
+    let last = if color == WHITE { RANK_8 } else { RANK_1 };
 
+    (targets & !last, targets & last)
 }
 
-fn neighborhood_attacks(
-    game: &Game,
-    s: Square,
-    side: ColorSide,
-    dirs: Vec<Dir>,
-) -> Bitboard {
-    let mut legal_squares: Vec<Square> = vec![];
-    for dir in dirs {
-        match s.get(&dir) {
-            Some(target) => {
-                match game.position.from_square(target) {
-                    None => legal_squares.push(target),
-                    Some(piece) => {
-                        if piece.color() != side {
-                            legal_squares.push(target)
-                        }
-                    }
-                }
-            }
-            None => {},
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_to_move() {
+        let white = Game::from_fen("4k3/8/8/8/8/8/3n4/3QK3 w - - 0 1").unwrap();
+        let black = Game::from_fen("4k3/8/8/8/8/8/3n4/3QK3 b - - 0 1").unwrap();
+
+        assert!(piece_attacks(QUEEN, &white, D1).at(D2));
+        assert!(!piece_attacks(QUEEN, &white, D1).at(E1));
+        assert!(!piece_attacks(QUEEN, &black, D1).at(D2));
+        assert!(piece_attacks(QUEEN, &black, D1).at(E1));
+    }
+
+    #[test]
+    fn sliders_stop_at_any_piece() {
+        // The rook doesn't see past the black king, nor the
+        // bishop past its own pawn.
+        let game = Game::from_fen("4k3/8/8/8/8/8/1P6/R3K3 w - - 0 1").unwrap();
+        let occupancy = game.position.occupancy();
+
+        assert_eq!(slider_attacks(ROOK, A1, occupancy), Bitboard::from_squares(vec![A2, A3, A4, A5, A6, A7, A8, B1, C1, D1, E1]));
+        assert_eq!(slider_attacks(BISHOP, A1, occupancy), Bitboard::from_squares(vec![B2]));
+        assert!(!side_attacks(ROOK, &Game::from_fen("r3k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap().position, A8, BLACK).at(F8));
+    }
+
+    #[test]
+    fn pawns() {
+        let game = Game::from_fen("4k3/1p1p3P/3N4/8/p7/1P6/P3P1p1/4K2R b - - 0 1").unwrap();
+        let position = &game.position;
+        let white = position.from_piece(Piece::WhitePawn);
+        let black = position.from_piece(Piece::BlackPawn);
+        let empty = !position.occupancy();
+
+        assert_eq!(pawn_pushes(WHITE, white, empty), Bitboard::from_squares(vec![A3, B4, E3, H8]));
+        assert_eq!(pawn_pushes(BLACK, black, empty), Bitboard::from_squares(vec![B6, A3, G1]));
+        assert_eq!(pawn_double_pushes(WHITE, white, empty), Bitboard::from_squares(vec![E4]));
+        assert_eq!(pawn_double_pushes(BLACK, black, empty), Bitboard::from_squares(vec![B5]));
+
+        assert_eq!(pawn_captures_left(WHITE, white, position[BLACK]), Bitboard::from_squares(vec![A4]));
+        assert_eq!(pawn_captures_right(WHITE, white, position[BLACK]), Bitboard::EMPTY);
+        assert_eq!(pawn_captures_left(BLACK, black, position[WHITE]), Bitboard::EMPTY);
+        assert_eq!(pawn_captures_right(BLACK, black, position[WHITE]), Bitboard::from_squares(vec![B3, H1]));
+        assert_eq!(pawn_captures_right(BLACK, black, square_bitboard(D6)), Bitboard::EMPTY);
+        assert_eq!(pawn_captures_left(BLACK, black, square_bitboard(C6)), Bitboard::from_squares(vec![C6]));
+
+        let (others, promotions) = split_promotions(BLACK, pawn_pushes(BLACK, black, empty));
+        assert_eq!(others, Bitboard::from_squares(vec![B6, A3]));
+        assert_eq!(promotions, Bitboard::from_squares(vec![G1]));
+
+        let game = Game::from_fen("4k3/8/8/2PpP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let white = game.position.from_piece(Piece::WhitePawn);
+        assert_eq!(pawn_en_passant(WHITE, white, game.position.en_passant()), Bitboard::from_squares(vec![C5, E5]));
+        assert_eq!(pawn_en_passant(WHITE, white, None), Bitboard::EMPTY);
     }
 
-    Bitboard::from_squares(legal_squares)
+    #[test]
+    fn xray() {
+        let occupancy = Bitboard::from_squares(vec![A1, C1, E1, G1, A4]);
+
+        assert_eq!(xray_attacks(ROOK, A1, occupancy, square_bitboard(C1)), Bitboard::from_squares(vec![D1, E1]));
+        assert_eq!(xray_attacks(ROOK, A1, occupancy, Bitboard::from_squares(vec![C1, E1])), Bitboard::from_squares(vec![D1, E1]));
+        assert_eq!(xray_attacks(ROOK, A1, occupancy, square_bitboard(A4)), Bitboard::from_squares(vec![A5, A6, A7, A8]));
+        // E1 is not the first blocker, so it's not x-rayed.
+        assert_eq!(xray_attacks(ROOK, A1, occupancy, square_bitboard(E1)), Bitboard::EMPTY);
+        assert_eq!(xray_attacks(QUEEN, A1, occupancy, square_bitboard(C1)), Bitboard::from_squares(vec![D1, E1]));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,36 @@
+use super::super::bitboard::*;
+use super::super::square::*;
+use super::magic;
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+use super::pext;
+
+// Sliding attacks used by the rest of the crate. With the
+// "pext" feature enabled and a CPU which supports BMI2,
+// lookups are done with the pext backend, otherwise with
+// the portable magic bitboards.
+
+/// Returns rook attacks from square with given occupancy.
+/// The first blocker on each ray is included.
+pub fn rook_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+    if pext::is_supported() {
+        return pext::rook_attacks(square, occupancy);
+    }
+
+    magic::rook_attacks(square, occupancy)
+}
+
+/// Returns bishop attacks from square with given occupancy.
+/// The first blocker on each ray is included.
+pub fn bishop_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+    if pext::is_supported() {
+        return pext::bishop_attacks(square, occupancy);
+    }
+
+    magic::bishop_attacks(square, occupancy)
+}
+
+pub fn queen_attacks(square: Square, occupancy: Bitboard) -> Bitboard {
+    rook_attacks(square, occupancy) | bishop_attacks(square, occupancy)
+}
\ No newline at end of file
@@ -1,73 +1,575 @@
+use std::ops::Index;
+
 use super::bitboard::*;
+use super::castling::*;
+use super::geometry::between;
 use super::piece::*;
+use super::piece::{attack_tables, sliders};
+use super::piece::piece_attacks::{pawn_en_passant, slider_attacks};
 use super::square::*;
 use super::color_side::*;
+use super::zobrist;
 
-
-/// Position is an array with 8 bitboards:
-/// 
+/// Position keeps the pieces on the board in two forms,
+/// 8 bitboards and a mailbox, which are kept in sync by
+/// put_piece, remove_piece and move_piece.
+///
+/// Bitboards:
+///
 /// First 6 items for each piece kind.
 /// Last 2 items for each color.
-/// 
+///
 /// The order for piece kinds should follow the order
 /// used in piece.rs. (index: 0 to 5)
-/// 
-/// The order for color sides is WHITE (index: 6) and 
+///
+/// The order for color sides is WHITE (index: 6) and
 /// then BLACK (index: 7), by convention.
+///
+/// The mailbox has the piece on each square (or None),
+/// so looking up a square doesn't have to go through
+/// all the bitboards.
+///
+/// Besides the pieces, a position has the side to move,
+/// castling rights and en passant square, and a Zobrist
+/// hash of all of them. They all change through methods,
+/// which keep the hash up to date.
+///
+/// Checkers and pinned pieces are cached too, but they
+/// depend on the whole position, so they're only brought
+/// up to date by update_checks(), which make_move calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    bitboards: [Bitboard; 8],
+    mailbox: [Option<Piece>; 64],
+    occupancy: Bitboard,
+    side_to_move: ColorSide,
+    castling_rights: CastlingRights,
+    castling_rooks: [Square; 4],      // starting square of the rook of
+                                      // each right, see castling_rook.
+    en_passant: Option<Square>,       // square behind a pawn which just
+                                      // made a double push.
+    hash: u64,                        // without the en passant file,
+                                      // see hash().
+    checkers: Bitboard,               // enemy pieces attacking the king
+                                      // of the side to move.
+    pinned: [Bitboard; 2],            // pieces of each color pinned to
+                                      // their own king.
+}
+
+/// Position can be indexed by a piece kind or a color
+/// side directly, without casting them to usize.
+impl Index<PieceKind> for Position {
+    type Output = Bitboard;
+
+    fn index(&self, kind: PieceKind) -> &Bitboard {
+        &self.bitboards[kind.index()]
+    }
+}
+
+impl Index<ColorSide> for Position {
+    type Output = Bitboard;
+
+    fn index(&self, color: ColorSide) -> &Bitboard {
+        &self.bitboards[6 + color.index()]
+    }
+}
 
-pub type Position = [Bitboard; 8];
-
-pub trait Positionable {
-    fn initial() -> Position {
-        [
-            Bitboard::initial_from_piece_kind(PAWN),
-            Bitboard::initial_from_piece_kind(KING),
-            Bitboard::initial_from_piece_kind(ROOK),
-            Bitboard::initial_from_piece_kind(QUEEN),
-            Bitboard::initial_from_piece_kind(KNIGHT),
-            Bitboard::initial_from_piece_kind(BISHOP),
-            Bitboard::initial_from_piece_color(WHITE),
-            Bitboard::initial_from_piece_color(BLACK),
-        ] as Position
-    }
-    fn from_piece_color(&self, color: ColorSide) -> Bitboard;
-    fn from_piece_kind(&self, kind: PieceKind) -> Bitboard;
-    fn from_piece(&self, piece: Piece) -> Bitboard;
-    fn from_square(&self, square: Square) -> Option<Piece>;
+impl Default for Position {
+    fn default() -> Position {
+        Position::new()
+    }
 }
 
-impl Positionable for Position {
-    fn from_piece_color(&self, color: ColorSide) -> Bitboard {
-        if color == WHITE {
-            self[6]
+impl Position {
+    /// Returns an empty board, white to move with no
+    /// castling rights.
+    pub fn new() -> Position {
+        Position {
+            bitboards: [Bitboard::EMPTY; 8],
+            mailbox: [None; 64],
+            occupancy: Bitboard::EMPTY,
+            side_to_move: WHITE,
+            castling_rights: NO_CASTLING,
+            castling_rooks: [H1, A1, H8, A8],
+            en_passant: None,
+            hash: 0,
+            checkers: Bitboard::EMPTY,
+            pinned: [Bitboard::EMPTY; 2],
+        }
+    }
+
+    /// Returns the initial standard chess position.
+    pub fn initial() -> Position {
+        let mut position = Position::new();
+
+        for piece in Piece::iter() {
+            let squares = Bitboard::initial_from_piece_kind(piece.kind()) & Bitboard::initial_from_piece_color(piece.color());
+            for square in squares {
+                position.put_piece(piece, square);
+            }
+        }
+        position.set_castling_rights(ALL_CASTLING);
+        position.update_checks();
+
+        position
+    }
+
+    pub fn from_piece_color(&self, color: ColorSide) -> Bitboard {
+        self[color]
+    }
+
+    pub fn from_piece_kind(&self, kind: PieceKind) -> Bitboard {
+        self[kind]
+    }
+
+    pub fn from_piece(&self, piece: Piece) -> Bitboard {
+        self[piece.color()] & self[piece.kind()]
+    }
+
+    /// Returns the piece on square, or None if it's
+    /// empty.
+    pub fn from_square(&self, square: Square) -> Option<Piece> {
+        self.mailbox[square.index()]
+    }
+
+    /// Returns bitboard of all occupied squares.
+    pub fn occupancy(&self) -> Bitboard {
+        self.occupancy
+    }
+
+    pub fn side_to_move(&self) -> ColorSide {
+        self.side_to_move
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    /// Returns the square the rook of a single castling
+    /// right starts on. That's the corner on its side in
+    /// standard chess, but can be any square of the back
+    /// rank in Chess960.
+    pub fn castling_rook(&self, right: CastlingRights) -> Square {
+        self.castling_rooks[castling_index(right)]
+    }
+
+    /// Sets the starting square of the rook of a single
+    /// castling right. The rook squares don't change during
+    /// a game, so they're not part of the hash.
+    pub fn set_castling_rook(&mut self, right: CastlingRights, square: Square) {
+        self.castling_rooks[castling_index(right)] = square;
+    }
+
+    /// Returns true if castling can't be written as the
+    /// king move of standard chess: for some castling right
+    /// still held, the king isn't on the e-file or the rook
+    /// isn't in the corner.
+    pub fn is_chess960(&self) -> bool {
+        let standard = [(E1, H1), (E1, A1), (E8, H8), (E8, A8)];
+
+        CASTLING_RIGHTS.iter().zip(standard).any(|(&right, (king, rook))| {
+            let color = if right & (WHITE_KINGSIDE | WHITE_QUEENSIDE) != 0 { WHITE } else { BLACK };
+
+            self.castling_rights & right != 0
+                && (self.castling_rook(right) != rook || self.mailbox[king.index()] != Some(Piece::new(KING, color)))
+        })
+    }
+
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Returns the Zobrist hash of the position, which is
+    /// kept up to date by every change to it. The en passant
+    /// file only counts if a pawn of the side to move can
+    /// capture there, otherwise the position is the same as
+    /// without it.
+    pub fn hash(&self) -> u64 {
+        self.hash ^ self.en_passant_hash()
+    }
+
+    /// Computes the Zobrist hash of the position from
+    /// scratch. Should always be equal to hash().
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = zobrist::castling(self.castling_rights) ^ self.en_passant_hash();
+
+        if self.side_to_move == BLACK {
+            hash ^= zobrist::black_to_move();
+        }
+        for square in self.occupancy {
+            hash ^= zobrist::piece_square(self.mailbox[square.index()].unwrap(), square);
+        }
+
+        hash
+    }
+
+    pub fn set_side_to_move(&mut self, side: ColorSide) {
+        if side != self.side_to_move {
+            self.hash ^= zobrist::black_to_move();
+        }
+        self.side_to_move = side;
+    }
+
+    pub fn set_castling_rights(&mut self, rights: CastlingRights) {
+        self.hash ^= zobrist::castling(self.castling_rights) ^ zobrist::castling(rights);
+        self.castling_rights = rights;
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.en_passant = square;
+    }
+
+    /// Returns the en passant part of the hash, which depends
+    /// on the pawns around the square and so isn't kept in
+    /// the incremental hash.
+    fn en_passant_hash(&self) -> u64 {
+        let side = self.side_to_move;
+        let pawns = self.from_piece(Piece::new(PAWN, side));
+
+        if pawn_en_passant(side, pawns, self.en_passant).is_empty() {
+            0
         } else {
-            self[7]
+            zobrist::en_passant(self.en_passant)
         }
     }
 
-    fn from_piece_kind(&self, kind: PieceKind) -> Bitboard {
-        self[kind as usize]
+    /// Returns pieces of both colors which attack square,
+    /// with sliders blocked by occupancy rather than the
+    /// actual pieces on the board.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::bitboard::Bitboard;
+    /// # use bitboard::game::Game;
+    /// # use bitboard::square::*;
+    /// # fn main() -> Result<(), bitboard::fen::FenError> {
+    /// let position = Game::from_fen("4k3/8/8/8/8/2n5/8/R3K3 w - - 0 1")?.position;
+    ///
+    /// assert_eq!(position.attackers_to(D1, position.occupancy()), Bitboard::from_squares(vec![A1, C3, E1]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn attackers_to(&self, square: Square, occupancy: Bitboard) -> Bitboard {
+        let straight = self[ROOK] | self[QUEEN];
+        let diagonal = self[BISHOP] | self[QUEEN];
+
+        attack_tables::pawn_attacks(square, BLACK) & self.from_piece(Piece::WhitePawn)
+            | attack_tables::pawn_attacks(square, WHITE) & self.from_piece(Piece::BlackPawn)
+            | attack_tables::knight_attacks(square) & self[KNIGHT]
+            | attack_tables::king_attacks(square) & self[KING]
+            | sliders::rook_attacks(square, occupancy) & straight
+            | sliders::bishop_attacks(square, occupancy) & diagonal
     }
 
-    fn from_piece(&self, piece: Piece) -> Bitboard {
-        self.from_piece_color(piece.color()) & self.from_piece_kind(piece.kind())
+    /// Returns true if any piece of color attacks square.
+    pub fn is_square_attacked_by(&self, square: Square, color: ColorSide) -> bool {
+        !(self.attackers_to(square, self.occupancy) & self[color]).is_empty()
     }
 
-    fn from_square(&self, square: Square) -> Option<Piece> {
-        let mut kind: Option<PieceKind> = None;
-        for p in 0..=5 {
-            if self.from_piece_kind(p as PieceKind).at(square) {
-                kind = Some(p as PieceKind);
-                break
-            }
+    /// Returns squares the king of color can't step to,
+    /// because an enemy piece attacks them.
+    ///
+    /// The king is taken off the occupancy first: a king in
+    /// check from a slider can't step back along the ray,
+    /// even though the square behind it looks blocked by
+    /// the king itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::game::Game;
+    /// # use bitboard::square::*;
+    /// # fn main() -> Result<(), bitboard::fen::FenError> {
+    /// let position = Game::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1")?.position;
+    ///
+    /// assert!(position.king_danger_squares(WHITE).at(F1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn king_danger_squares(&self, color: ColorSide) -> Bitboard {
+        let enemy = color.opposite();
+        let occupancy = self.occupancy & !self.from_piece(Piece::new(KING, color));
+        let mut danger = Bitboard::EMPTY;
+
+        for square in self[enemy] {
+            danger |= match self.mailbox[square.index()].unwrap().kind() {
+                PAWN => attack_tables::pawn_attacks(square, enemy),
+                KNIGHT => attack_tables::knight_attacks(square),
+                KING => attack_tables::king_attacks(square),
+                kind => slider_attacks(kind, square, occupancy),
+            };
+        }
+
+        danger
+    }
+
+    /// Returns enemy pieces giving check to the king of the
+    /// side to move.
+    pub fn checkers(&self) -> Bitboard {
+        self.checkers
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        !self.checkers.is_empty()
+    }
+
+    /// Returns pieces of color which are pinned to their
+    /// king by an enemy slider, so moving them off the line
+    /// between the two would leave the king in check.
+    pub fn pinned(&self, color: ColorSide) -> Bitboard {
+        self.pinned[color.index()]
+    }
+
+    /// Recomputes checkers and pinned pieces. Must be called
+    /// after changing the pieces or the side to move, before
+    /// checkers(), is_in_check() or pinned() are used.
+    pub fn update_checks(&mut self) {
+        self.checkers = self.compute_checkers();
+        self.pinned = [self.compute_pinned(WHITE), self.compute_pinned(BLACK)];
+    }
+
+    fn compute_checkers(&self) -> Bitboard {
+        let side = self.side_to_move;
+
+        match self.from_piece(Piece::new(KING, side)).lsb() {
+            Some(king) => self.attackers_to(king, self.occupancy) & self[side.opposite()],
+            None => Bitboard::EMPTY,
+        }
+    }
+
+    /// Pinned pieces are found from the king: every enemy
+    /// slider which would attack it on an empty board, with
+    /// exactly one piece between them, pins that piece if
+    /// it belongs to color.
+    fn compute_pinned(&self, color: ColorSide) -> Bitboard {
+        let king = match self.from_piece(Piece::new(KING, color)).lsb() {
+            Some(king) => king,
+            None => return Bitboard::EMPTY,
         };
 
-        if kind == None {
-            return None
-        } else if self.from_piece_color(WHITE).at(square) {
-            Some(Piece::new(kind.unwrap(), WHITE))
-        } else {
-            Some(Piece::new(kind.unwrap(), BLACK))
+        let enemies = self[color.opposite()];
+        let snipers = sliders::rook_attacks(king, Bitboard::EMPTY) & (self[ROOK] | self[QUEEN]) & enemies
+            | sliders::bishop_attacks(king, Bitboard::EMPTY) & (self[BISHOP] | self[QUEEN]) & enemies;
+
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = between(king, sniper) & self.occupancy;
+            if blockers.count() == 1 {
+                pinned |= blockers & self[color];
+            }
+        }
+
+        pinned
+    }
+
+    /// Places piece on square, which should be empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::piece::Piece;
+    /// # use bitboard::position::Position;
+    /// # use bitboard::square::*;
+    /// let mut position = Position::new();
+    /// position.put_piece(Piece::WhiteKing, E1);
+    ///
+    /// assert_eq!(position.from_square(E1), Some(Piece::WhiteKing));
+    /// ```
+    pub fn put_piece(&mut self, piece: Piece, square: Square) {
+        debug_assert!(self.mailbox[square.index()].is_none(), "{} is not empty", square);
+
+        self.toggle(piece, square);
+        self.mailbox[square.index()] = Some(piece);
+    }
+
+    /// Takes the piece off square and returns it. Panics
+    /// if square is empty.
+    pub fn remove_piece(&mut self, square: Square) -> Piece {
+        let piece = self.mailbox[square.index()].take().expect("no piece to remove");
+        self.toggle(piece, square);
+
+        piece
+    }
+
+    /// Moves the piece on from to the empty square to,
+    /// and returns it.
+    pub fn move_piece(&mut self, from: Square, to: Square) -> Piece {
+        let piece = self.remove_piece(from);
+        self.put_piece(piece, to);
+
+        piece
+    }
+
+    /// Checks the bitboards and the mailbox agree with each
+    /// other: kind and color bitboards are disjoint, their
+    /// unions are the occupancy, every square of them has
+    /// the same piece in the mailbox and the hash and the
+    /// cached checkers and pins are right.
+    /// It looks at every square, so make_move and
+    /// unmake_move only call it in debug builds.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut kinds = Bitboard::EMPTY;
+        for kind in PieceKind::iter() {
+            if !(kinds & self[kind]).is_empty() {
+                return Err(format!("{:?} bitboard overlaps other kinds", kind));
+            }
+            kinds |= self[kind];
+        }
+
+        if !(self[WHITE] & self[BLACK]).is_empty() {
+            return Err("white and black bitboards overlap".to_string());
+        }
+        if kinds != self.occupancy || (self[WHITE] | self[BLACK]) != self.occupancy {
+            return Err("occupancy does not match the bitboards".to_string());
+        }
+
+        for square in Square::all() {
+            let on_bitboards = Piece::iter().find(|&piece| self.from_piece(piece).contains(square));
+            if on_bitboards != self.mailbox[square.index()] {
+                return Err(format!("mailbox has {:?} on {}, bitboards have {:?}", self.mailbox[square.index()], square, on_bitboards));
+            }
+        }
+
+        if self.hash() != self.compute_hash() {
+            return Err("hash does not match the position".to_string());
         }
+        if self.checkers != self.compute_checkers() || self.pinned != [self.compute_pinned(WHITE), self.compute_pinned(BLACK)] {
+            return Err("checkers or pinned pieces are out of date".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Flips square on the bitboards of piece and on the
+    /// occupancy.
+    fn toggle(&mut self, piece: Piece, square: Square) {
+        let bit = square_bitboard(square);
+
+        self.bitboards[piece.kind().index()] ^= bit;
+        self.bitboards[6 + piece.color().index()] ^= bit;
+        self.occupancy ^= bit;
+        self.hash ^= zobrist::piece_square(piece, square);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn initial_pieces_on_their_squares() {
+        let position = Position::initial();
+        let back_rank = [ROOK, KNIGHT, BISHOP, QUEEN, KING, BISHOP, KNIGHT, ROOK];
+
+        for square in Square::all() {
+            let expected = match square.rank() {
+                0 => Some(Piece::new(back_rank[square.file() as usize], WHITE)),
+                1 => Some(Piece::new(PAWN, WHITE)),
+                6 => Some(Piece::new(PAWN, BLACK)),
+                7 => Some(Piece::new(back_rank[square.file() as usize], BLACK)),
+                _ => None,
+            };
+
+            assert_eq!(position.from_square(square), expected, "square {}", square);
+        }
+
+        assert_eq!(position.from_piece_color(WHITE), Bitboard(0x0000_0000_0000_FFFF));
+        assert_eq!(position.from_piece_color(BLACK), Bitboard(0xFFFF_0000_0000_0000));
+        assert_eq!(position.occupancy(), Bitboard(0xFFFF_0000_0000_FFFF));
+        assert_eq!(position.validate(), Ok(()));
+    }
+
+    #[test]
+    fn put_remove_move() {
+        let mut position = Position::new();
+
+        position.put_piece(Piece::WhiteKnight, G1);
+        position.put_piece(Piece::BlackPawn, F3);
+        assert_eq!(position.occupancy(), Bitboard::from_squares(vec![G1, F3]));
+
+        assert_eq!(position.remove_piece(F3), Piece::BlackPawn);
+        assert_eq!(position.move_piece(G1, F3), Piece::WhiteKnight);
+
+        assert_eq!(position.from_square(G1), None);
+        assert_eq!(position.from_square(F3), Some(Piece::WhiteKnight));
+        assert_eq!(position[KNIGHT], Bitboard::from_squares(vec![F3]));
+        assert_eq!(position[BLACK], Bitboard::EMPTY);
+        assert_eq!(position.validate(), Ok(()));
+    }
+
+    #[test]
+    fn checks_and_pins() {
+        let position = |fen: &str| Game::from_fen(fen).unwrap().position;
+
+        // Not a reachable position, but the white king is in
+        // check three times, and the knight on e2 is pinned
+        // by the rook on e8.
+        let checked = position("4r1k1/8/8/8/1b6/3n4/4N3/r3K3 w - - 0 1");
+        assert_eq!(checked.checkers(), Bitboard::from_squares(vec![A1, B4, D3]));
+        assert!(checked.is_in_check());
+        assert_eq!(checked.pinned(WHITE), Bitboard::from_squares(vec![E2]));
+        assert_eq!(checked.pinned(BLACK), Bitboard::EMPTY);
+
+        // Two pieces between the king and the queen, so
+        // neither is pinned. Without the knight, the bishop is.
+        let quiet = position("6k1/5b2/4n3/8/2Q5/8/8/4K3 b - - 0 1");
+        assert!(!quiet.is_in_check());
+        assert_eq!(quiet.pinned(BLACK), Bitboard::EMPTY);
+        assert_eq!(quiet.pinned(WHITE), Bitboard::EMPTY);
+
+        let pinned = position("6k1/5b2/8/8/2Q5/8/8/4K3 b - - 0 1");
+        assert_eq!(pinned.pinned(BLACK), Bitboard::from_squares(vec![F7]));
+        assert!(pinned.is_square_attacked_by(D5, WHITE));
+        assert!(!pinned.is_square_attacked_by(G8, WHITE));
+        assert!(pinned.is_square_attacked_by(E6, BLACK));
+    }
+
+    #[test]
+    fn king_danger() {
+        let position = Game::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap().position;
+        let danger = position.king_danger_squares(WHITE);
+
+        // F1 is behind the king on the checking ray, so it's
+        // in danger although the rook doesn't attack it with
+        // the king on the board.
+        assert!(danger.at(F1));
+        assert!(!position.is_square_attacked_by(F1, BLACK));
+        assert!(danger.at(D1) && danger.at(A2));
+        assert!(!danger.at(E2) && !danger.at(F2));
+
+        // Pawns attack diagonally only, the black king
+        // guards its own squares.
+        let position = Game::from_fen("8/8/8/8/3k4/4p3/8/4K3 w - - 0 1").unwrap().position;
+        let danger = position.king_danger_squares(WHITE);
+        assert!(danger.at(D2) && danger.at(F2) && danger.at(E3));
+        assert!(!danger.at(E2));
+    }
+
+    #[test]
+    fn checks_follow_moves() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let m = game.find_move("d2d7".parse().unwrap()).unwrap();
+
+        game.make_move(m);
+        assert_eq!(game.position.checkers(), Bitboard::from_squares(vec![D7]));
+        assert_eq!(game.position.validate(), Ok(()));
+
+        game.unmake_move();
+        assert!(!game.position.is_in_check());
+        assert_eq!(game.position.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_catches_mismatch() {
+        let mut position = Position::initial();
+        position.mailbox[E4.index()] = Some(Piece::WhiteQueen);
+        assert!(position.validate().is_err());
+
+        let mut position = Position::initial();
+        position.bitboards[KNIGHT.index()] |= square_bitboard(A1);
+        assert!(position.validate().is_err());
     }
 }
\ No newline at end of file
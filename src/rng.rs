@@ -0,0 +1,27 @@
+/// Small xorshift64* pseudo random number generator.
+/// Deterministic for a given seed, which is what magic
+/// number search and randomized tests need.
+pub struct Rng(u64);
+
+impl Rng {
+    /// Returns a new Rng. seed must not be zero.
+    pub const fn new(seed: u64) -> Rng {
+        assert!(seed != 0);
+
+        Rng(seed)
+    }
+
+    pub const fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a random number with about 8 of its
+    /// 64 bits set.
+    pub fn sparse_u64(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,356 @@
+use std::fmt::{self, Display};
+use super::color_side::*;
+use super::game::Game;
+use super::moves::*;
+use super::piece::*;
+use super::square::*;
+
+/// Error returned by Game::parse_san.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    Invalid(String),                  // not a move in SAN at all.
+    Illegal(String),                  // no legal move matches.
+    Ambiguous(String, Vec<Move>),     // more than one legal move
+                                      // matches, all of them given.
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Invalid(san) => write!(f, "invalid SAN '{}'", san),
+            SanError::Illegal(san) => write!(f, "illegal move '{}'", san),
+            SanError::Ambiguous(san, moves) => {
+                let moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+                write!(f, "ambiguous move '{}', could be {}", san, moves.join(" or "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+/// What a SAN string says about a move, before looking
+/// for it among the legal moves.
+struct SanMove {
+    kind: PieceKind,
+    file: Option<u8>,                 // disambiguation, or the file
+    rank: Option<u8>,                 // a pawn captures from.
+    to: Square,
+    promotion: Option<PieceKind>,
+}
+
+impl Game {
+    /// Returns the legal move written as san in Standard
+    /// Algebraic Notation, like "Nbd7", "exd6", "O-O-O" or
+    /// "e8=Q+".
+    ///
+    /// Common variants are accepted too: castling with
+    /// zeros, promotion without "=", lowercase piece letters
+    /// (a lowercase "b" is a bishop only if it can't be a
+    /// pawn), long algebraic "Ng1-f3", an "e.p." suffix and
+    /// annotations like "!?". Check marks and the capture
+    /// sign are not checked against the move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::san::SanError;
+    /// # use bitboard::square::*;
+    /// let game = Game::default();
+    ///
+    /// assert_eq!(game.parse_san("Nf3"), Ok(Move::new(G1, F3, QUIET)));
+    /// assert!(matches!(game.parse_san("Nd2"), Err(SanError::Illegal(_))));
+    /// ```
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let text = san.trim();
+        let text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+        let text = text.trim_end_matches(['+', '#', '!', '?']);
+
+        let legal = self.legal_moves();
+
+        let castle = match text {
+            "O-O" | "0-0" | "o-o" => Some(KING_CASTLE),
+            "O-O-O" | "0-0-0" | "o-o-o" => Some(QUEEN_CASTLE),
+            _ => None,
+        };
+        if let Some(flag) = castle {
+            return legal.into_iter().find(|m| m.flag() == flag).ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let parsed = parse_text(text).ok_or_else(|| SanError::Invalid(san.to_string()))?;
+        let mut candidates = self.matching(&legal, &parsed);
+
+        // "bxc3" is a pawn capture, unless no pawn can make it.
+        if candidates.is_empty() && text.starts_with('b') {
+            if let Some(bishop) = parse_text(&format!("B{}", &text[1..])) {
+                candidates = self.matching(&legal, &bishop);
+            }
+        }
+
+        match candidates.len() {
+            0 => Err(SanError::Illegal(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::Ambiguous(san.to_string(), candidates)),
+        }
+    }
+
+    /// Returns m, which must be legal, in Standard Algebraic
+    /// Notation, with only as much disambiguation as needed
+    /// (file first, then rank, then both) and a "+" or "#"
+    /// suffix for check and mate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::game::Game;
+    /// # use bitboard::moves::*;
+    /// # use bitboard::square::*;
+    /// let game = Game::default();
+    ///
+    /// assert_eq!(game.to_san(Move::new(G1, F3, QUIET)), "Nf3");
+    /// ```
+    pub fn to_san(&self, m: Move) -> String {
+        let mut san = if m.is_castle() {
+            if m.flag() == KING_CASTLE { "O-O".to_string() } else { "O-O-O".to_string() }
+        } else {
+            let kind = self.position.from_square(m.from()).expect("no piece to move").kind();
+            let mut san = String::new();
+
+            if kind == PAWN {
+                if m.is_capture() {
+                    san.push(m.from().file_as_char().to_ascii_lowercase());
+                }
+            } else {
+                san.push(Piece::new(kind, WHITE).to_fen_char());
+                san.push_str(&self.disambiguation(m, kind));
+            }
+
+            if m.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&m.to().to_string());
+
+            if let Some(promotion) = m.promotion() {
+                san.push('=');
+                san.push(Piece::new(promotion, WHITE).to_fen_char());
+            }
+
+            san
+        };
+
+        // Check and mate only depend on the position, so the
+        // history is left behind.
+        let mut after = Game { position: self.position.clone(), history: vec![], ..*self };
+        after.make_move(m);
+        if after.position.is_in_check() {
+            san.push(if after.legal_moves().is_empty() { '#' } else { '+' });
+        }
+
+        san
+    }
+
+    /// Returns legal moves which fit what parsed says.
+    fn matching(&self, legal: &MoveList, parsed: &SanMove) -> Vec<Move> {
+        legal
+            .iter()
+            .copied()
+            .filter(|m| {
+                !m.is_castle()
+                    && self.position.from_square(m.from()).map(|piece| piece.kind()) == Some(parsed.kind)
+                    && m.to() == parsed.to
+                    && m.promotion() == parsed.promotion
+                    && parsed.file.is_none_or(|file| m.from().file() == file)
+                    && parsed.rank.is_none_or(|rank| m.from().rank() == rank)
+            })
+            .collect()
+    }
+
+    /// Returns what to write after the piece letter of m so
+    /// no other piece of the same kind could make it.
+    fn disambiguation(&self, m: Move, kind: PieceKind) -> String {
+        let others: Vec<Square> = self.legal_moves()
+            .into_iter()
+            .filter(|other| {
+                !other.is_castle()
+                    && other.to() == m.to()
+                    && other.from() != m.from()
+                    && self.position.from_square(other.from()).map(|piece| piece.kind()) == Some(kind)
+            })
+            .map(|other| other.from())
+            .collect();
+
+        let from = m.from();
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.file() != from.file()) {
+            from.file_as_char().to_ascii_lowercase().to_string()
+        } else if others.iter().all(|other| other.rank() != from.rank()) {
+            (from.rank() + 1).to_string()
+        } else {
+            from.to_string()
+        }
+    }
+}
+
+/// Parses SAN of a piece or pawn move (not castling)
+/// without check marks, or returns None if text isn't one.
+fn parse_text(text: &str) -> Option<SanMove> {
+    if !text.is_ascii() || text.is_empty() {
+        return None;
+    }
+
+    let piece_kind = |c: char| match c.to_ascii_uppercase() {
+        'N' => Some(KNIGHT),
+        'B' => Some(BISHOP),
+        'R' => Some(ROOK),
+        'Q' => Some(QUEEN),
+        'K' => Some(KING),
+        _ => None,
+    };
+
+    // A lowercase "b" is read as a pawn file here, parse_san
+    // tries a bishop after.
+    let first = text.chars().next().unwrap();
+    let (kind, rest) = match piece_kind(first) {
+        Some(kind) if first != 'b' => (kind, &text[1..]),
+        _ => (PAWN, text),
+    };
+
+    // Promotion, with or without "=".
+    let (rest, promotion) = match rest.char_indices().last() {
+        Some((i, c)) if kind == PAWN && !c.is_ascii_digit() => {
+            let rest = rest[..i].strip_suffix('=').unwrap_or(&rest[..i]);
+            match piece_kind(c) {
+                Some(promotion) if promotion != KING => (rest, Some(promotion)),
+                _ => return None,
+            }
+        }
+        _ => (rest, None),
+    };
+
+    let rest = rest.replace(['x', ':', '-'], "");
+    if rest.len() < 2 || rest.len() > 4 {
+        return None;
+    }
+    let to: Square = rest[rest.len() - 2..].parse().ok()?;
+
+    let (mut file, mut rank) = (None, None);
+    for c in rest[..rest.len() - 2].chars() {
+        match c {
+            'a'..='h' if file.is_none() && rank.is_none() => file = Some(c as u8 - b'a'),
+            '1'..='8' if rank.is_none() => rank = Some(c as u8 - b'1'),
+            _ => return None,
+        }
+    }
+
+    Some(SanMove { kind, file, rank, to, promotion })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(fen: &str) -> Game {
+        Game::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let start = Game::default();
+        assert_eq!(start.parse_san("e4"), Ok(Move::new(E2, E4, DOUBLE_PUSH)));
+        assert_eq!(start.parse_san("Nc3"), Ok(Move::new(B1, C3, QUIET)));
+        assert_eq!(start.parse_san("nc3"), Ok(Move::new(B1, C3, QUIET)));
+        assert_eq!(start.parse_san("Ng1-f3!?"), Ok(Move::new(G1, F3, QUIET)));
+
+        let kiwipete = game("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(kiwipete.parse_san("O-O"), Ok(Move::new(E1, H1, KING_CASTLE)));
+        assert_eq!(kiwipete.parse_san("0-0-0"), Ok(Move::new(E1, A1, QUEEN_CASTLE)));
+        assert_eq!(kiwipete.parse_san("Bxa6"), Ok(Move::new(E2, A6, CAPTURE)));
+        assert_eq!(kiwipete.parse_san("Qxh3"), Ok(Move::new(F3, H3, CAPTURE)));
+        assert_eq!(kiwipete.parse_san("gxh3"), Ok(Move::new(G2, H3, CAPTURE)));
+
+        let en_passant = game("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3");
+        assert_eq!(en_passant.parse_san("exd6 e.p."), Ok(Move::new(E5, D6, EN_PASSANT)));
+        assert_eq!(en_passant.parse_san("exd6"), Ok(Move::new(E5, D6, EN_PASSANT)));
+
+        let promotion = game("3r3k/4P3/8/8/8/8/8/K7 w - - 0 1");
+        assert_eq!(promotion.parse_san("e8=Q+"), Ok(Move::new_promotion(E7, E8, QUEEN, false)));
+        assert_eq!(promotion.parse_san("e8N"), Ok(Move::new_promotion(E7, E8, KNIGHT, false)));
+        assert_eq!(promotion.parse_san("exd8=q"), Ok(Move::new_promotion(E7, D8, QUEEN, true)));
+    }
+
+    #[test]
+    fn pawn_or_bishop() {
+        // bxc3 is the pawn, the bishop needs a capital B.
+        let both = game("4k3/8/8/8/1b6/2P5/1P6/4K3 b - - 0 1");
+        assert_eq!(both.parse_san("bxc3"), Ok(Move::new(B4, C3, CAPTURE)));
+
+        let both = game("4k3/8/8/8/1b6/2N5/1P6/4K3 w - - 0 1");
+        assert_eq!(both.parse_san("bxc3"), Err(SanError::Illegal("bxc3".to_string())));
+
+        let pawn = game("4k3/8/8/8/8/2n5/1P6/4K3 w - - 0 1");
+        assert_eq!(pawn.parse_san("bxc3"), Ok(Move::new(B2, C3, CAPTURE)));
+
+        let bishop = game("4k3/8/8/8/8/2n5/8/B3K3 w - - 0 1");
+        assert_eq!(bishop.parse_san("bxc3"), Ok(Move::new(A1, C3, CAPTURE)));
+    }
+
+    #[test]
+    fn errors() {
+        let game = game("4k3/8/8/8/8/8/1R5R/R3K3 w - - 0 1");
+
+        assert_eq!(game.parse_san("Rd2"), Err(SanError::Ambiguous("Rd2".to_string(), vec![Move::new(B2, D2, QUIET), Move::new(H2, D2, QUIET)])));
+        assert_eq!(game.parse_san("Ra2"), Err(SanError::Ambiguous("Ra2".to_string(), vec![Move::new(A1, A2, QUIET), Move::new(B2, A2, QUIET)])));
+        assert_eq!(game.parse_san("Rd3"), Err(SanError::Illegal("Rd3".to_string())));
+        assert_eq!(game.parse_san("O-O"), Err(SanError::Illegal("O-O".to_string())));
+        assert_eq!(game.parse_san("Zd2"), Err(SanError::Invalid("Zd2".to_string())));
+        assert_eq!(game.parse_san("Rd9"), Err(SanError::Invalid("Rd9".to_string())));
+        assert_eq!(game.parse_san(""), Err(SanError::Invalid("".to_string())));
+        assert_eq!(
+            game.parse_san("Rd2").unwrap_err().to_string(),
+            "ambiguous move 'Rd2', could be b2d2 or h2d2",
+        );
+
+        assert_eq!(game.parse_san("Rbd2"), Ok(Move::new(B2, D2, QUIET)));
+        assert_eq!(game.parse_san("R1a2"), Ok(Move::new(A1, A2, QUIET)));
+        assert_eq!(game.parse_san("Ra1a2"), Ok(Move::new(A1, A2, QUIET)));
+    }
+
+    #[test]
+    fn format() {
+        let start = Game::default();
+        assert_eq!(start.to_san(Move::new(E2, E4, DOUBLE_PUSH)), "e4");
+        assert_eq!(start.to_san(Move::new(G1, F3, QUIET)), "Nf3");
+
+        let kiwipete = game("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(kiwipete.to_san(Move::new(E1, H1, KING_CASTLE)), "O-O");
+        assert_eq!(kiwipete.to_san(Move::new(E1, A1, QUEEN_CASTLE)), "O-O-O");
+        assert_eq!(kiwipete.to_san(Move::new(G2, H3, CAPTURE)), "gxh3");
+        assert_eq!(kiwipete.to_san(Move::new(E5, F7, CAPTURE)), "Nxf7");
+        assert_eq!(kiwipete.to_san(Move::new(D5, E6, CAPTURE)), "dxe6");
+
+        // File, rank and both.
+        let rooks = game("4k3/8/8/8/8/R7/1R5R/R3K3 w - - 0 1");
+        assert_eq!(rooks.to_san(Move::new(B2, D2, QUIET)), "Rbd2");
+        assert_eq!(rooks.to_san(Move::new(B2, A2, QUIET)), "Rba2");
+        assert_eq!(rooks.to_san(Move::new(A1, A2, QUIET)), "R1a2");
+        let queens = game("8/7k/8/8/Q7/8/8/Q2QK3 w - - 0 1");
+        assert_eq!(queens.to_san(Move::new(D1, D4, QUIET)), "Qdd4");
+        assert_eq!(queens.to_san(Move::new(A4, D4, QUIET)), "Q4d4");
+        assert_eq!(queens.to_san(Move::new(A1, D4, QUIET)), "Qa1d4");
+
+        assert_eq!(game("3r3k/4P3/8/8/8/8/8/K7 w - - 0 1").to_san(Move::new_promotion(E7, D8, QUEEN, true)), "exd8=Q+");
+        assert_eq!(game("7k/8/6K1/8/8/8/8/R7 w - - 0 1").to_san(Move::new(A1, A8, QUIET)), "Ra8#");
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = game("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        for m in game.legal_moves() {
+            assert_eq!(game.parse_san(&game.to_san(m)), Ok(m), "{}", game.to_san(m));
+        }
+    }
+}
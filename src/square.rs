@@ -1,78 +1,132 @@
 #![allow(dead_code)]
 
+use std::fmt;
+use std::str::FromStr;
+
 use super::dir::{Dir, Dir::*};
 use move_possible::*;
 
-pub type Square = u8;
-
-pub const A1: Square = 0;
-pub const B1: Square = 1;
-pub const C1: Square = 2;
-pub const D1: Square = 3;
-pub const E1: Square = 4;
-pub const F1: Square = 5;
-pub const G1: Square = 6;
-pub const H1: Square = 7;
-pub const A2: Square = 8;
-pub const B2: Square = 9;
-pub const C2: Square = 10;
-pub const D2: Square = 11;
-pub const E2: Square = 12;
-pub const F2: Square = 13;
-pub const G2: Square = 14;
-pub const H2: Square = 15;
-pub const A3: Square = 16;
-pub const B3: Square = 17;
-pub const C3: Square = 18;
-pub const D3: Square = 19;
-pub const E3: Square = 20;
-pub const F3: Square = 21;
-pub const G3: Square = 22;
-pub const H3: Square = 23;
-pub const A4: Square = 24;
-pub const B4: Square = 25;
-pub const C4: Square = 26;
-pub const D4: Square = 27;
-pub const E4: Square = 28;
-pub const F4: Square = 29;
-pub const G4: Square = 30;
-pub const H4: Square = 31;
-pub const A5: Square = 32;
-pub const B5: Square = 33;
-pub const C5: Square = 34;
-pub const D5: Square = 35;
-pub const E5: Square = 36;
-pub const F5: Square = 37;
-pub const G5: Square = 38;
-pub const H5: Square = 39;
-pub const A6: Square = 40;
-pub const B6: Square = 41;
-pub const C6: Square = 42;
-pub const D6: Square = 43;
-pub const E6: Square = 44;
-pub const F6: Square = 45;
-pub const G6: Square = 46;
-pub const H6: Square = 47;
-pub const A7: Square = 48;
-pub const B7: Square = 49;
-pub const C7: Square = 50;
-pub const D7: Square = 51;
-pub const E7: Square = 52;
-pub const F7: Square = 53;
-pub const G7: Square = 54;
-pub const H7: Square = 55;
-pub const A8: Square = 56;
-pub const B8: Square = 57;
-pub const C8: Square = 58;
-pub const D8: Square = 59;
-pub const E8: Square = 60;
-pub const F8: Square = 61;
-pub const G8: Square = 62;
-pub const H8: Square = 63;
-
-pub struct Coordination(u8, u8);
-
-pub trait Squarable {
+/// A square of the board, from A1 (0) to H8 (63) going
+/// along the ranks. A Square is always on the board, so
+/// methods on it never have to check for that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+pub const A1: Square = Square(0);
+pub const B1: Square = Square(1);
+pub const C1: Square = Square(2);
+pub const D1: Square = Square(3);
+pub const E1: Square = Square(4);
+pub const F1: Square = Square(5);
+pub const G1: Square = Square(6);
+pub const H1: Square = Square(7);
+pub const A2: Square = Square(8);
+pub const B2: Square = Square(9);
+pub const C2: Square = Square(10);
+pub const D2: Square = Square(11);
+pub const E2: Square = Square(12);
+pub const F2: Square = Square(13);
+pub const G2: Square = Square(14);
+pub const H2: Square = Square(15);
+pub const A3: Square = Square(16);
+pub const B3: Square = Square(17);
+pub const C3: Square = Square(18);
+pub const D3: Square = Square(19);
+pub const E3: Square = Square(20);
+pub const F3: Square = Square(21);
+pub const G3: Square = Square(22);
+pub const H3: Square = Square(23);
+pub const A4: Square = Square(24);
+pub const B4: Square = Square(25);
+pub const C4: Square = Square(26);
+pub const D4: Square = Square(27);
+pub const E4: Square = Square(28);
+pub const F4: Square = Square(29);
+pub const G4: Square = Square(30);
+pub const H4: Square = Square(31);
+pub const A5: Square = Square(32);
+pub const B5: Square = Square(33);
+pub const C5: Square = Square(34);
+pub const D5: Square = Square(35);
+pub const E5: Square = Square(36);
+pub const F5: Square = Square(37);
+pub const G5: Square = Square(38);
+pub const H5: Square = Square(39);
+pub const A6: Square = Square(40);
+pub const B6: Square = Square(41);
+pub const C6: Square = Square(42);
+pub const D6: Square = Square(43);
+pub const E6: Square = Square(44);
+pub const F6: Square = Square(45);
+pub const G6: Square = Square(46);
+pub const H6: Square = Square(47);
+pub const A7: Square = Square(48);
+pub const B7: Square = Square(49);
+pub const C7: Square = Square(50);
+pub const D7: Square = Square(51);
+pub const E7: Square = Square(52);
+pub const F7: Square = Square(53);
+pub const G7: Square = Square(54);
+pub const H7: Square = Square(55);
+pub const A8: Square = Square(56);
+pub const B8: Square = Square(57);
+pub const C8: Square = Square(58);
+pub const D8: Square = Square(59);
+pub const E8: Square = Square(60);
+pub const F8: Square = Square(61);
+pub const G8: Square = Square(62);
+pub const H8: Square = Square(63);
+
+pub struct Coordination(pub u8, pub u8);
+
+/// Error returned when a string or number is not a
+/// square, the message says which input it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquareError(String);
+
+impl fmt::Display for SquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid square: {}", self.0)
+    }
+}
+
+impl std::error::Error for SquareError {}
+
+impl Square {
+    /// Returns the square with given index. Panics if
+    /// index is not below 64, use Square::try_from for
+    /// input which is not known to be valid.
+    pub const fn new(index: u8) -> Square {
+        assert!(index < 64);
+
+        Square(index)
+    }
+
+    /// Returns the square on given (zero-indexed) file and
+    /// rank, or None if one of them is out of board.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::square::*;
+    /// assert_eq!(Square::from_file_rank(4, 3), Some(E4));
+    /// assert_eq!(Square::from_file_rank(8, 0), None);
+    /// ```
+    pub const fn from_file_rank(file: u8, rank: u8) -> Option<Square> {
+        if file < 8 && rank < 8 { Some(Square(rank * 8 + file)) } else { None }
+    }
+
+    /// Returns an iterator over all squares from A1 to H8.
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    /// Returns the index of the square (0 for A1 to 63
+    /// for H8), for indexing tables and bitboards.
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
     /// Returns a new Coordination(file, rank). (zero-indexed)
     /// NOTE: I recommend using file() and rank() methods
     /// sice they don't require working with Coordination struct
@@ -81,30 +135,39 @@ pub trait Squarable {
     /// # Examples
     /// 
     /// ```
+    /// # use bitboard::square::*;
     /// let Coordination(file, rank) = B8.coord();
     /// 
     /// assert_eq!(file, 1);
     /// assert_eq!(rank, 7);
     /// ```
-    fn coord(&self) -> Coordination;
+    pub fn coord(&self) -> Coordination {
+        Coordination(self.file(), self.rank())
+    }
 
     /// Returns file of the square. again, zero-indexed.
     /// 
     /// # Examples
     /// 
     /// ```
+    /// # use bitboard::square::*;
     /// assert_eq!(A7.file(), 0);
     /// ```
-    fn file(&self) -> u8;
+    pub const fn file(&self) -> u8 {
+        self.0 % 8
+    }
 
     /// Returns rank of the square.
     /// 
     /// # Examples
     /// 
     /// ```
+    /// # use bitboard::square::*;
     /// assert_eq!(A7.rank(), 6);
     /// ```
-    fn rank(&self) -> u8;
+    pub const fn rank(&self) -> u8 {
+        self.0 / 8
+    }
 
     /// Returns file of the square with capital name
     /// of the file.
@@ -112,58 +175,59 @@ pub trait Squarable {
     /// # Examples
     /// 
     /// ```
+    /// # use bitboard::square::*;
     /// assert_eq!(A7.file_as_char(), 'A');
     /// ```
-    fn file_as_char(&self) -> char;
-
-    /// Returns Some(Square) if a square in dir: dir of
-    /// self is not out of board, if it's out of board
-    /// returns None.
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// assert_eq!(A2.get(Left), None);
-    /// assert_eq!(A2.get(Up), Some(A3));
-    /// ```
-    fn get(&self, dir: &Dir) -> Option<Square>;
-}
-
-impl Squarable for Square {
-    fn coord(&self) -> Coordination {
-        assert!(*self < 64);
-        
-        Coordination(self.file(), self.rank())
+    pub fn file_as_char(&self) -> char {
+        (b'A' + self.file()) as char
     }
 
-    fn file(&self) -> u8 {
-        assert!(*self < 64);
-
-        self % 8
+    /// Returns the square on the same file, mirrored
+    /// across the middle of the board (A1 <-> A8).
+    pub const fn flip_vertical(self) -> Square {
+        Square(self.0 ^ 56)
     }
 
-    fn rank(&self) -> u8 {
-        assert!(*self < 64);
+    /// Returns the square on the same rank, mirrored
+    /// across the middle of the board (A1 <-> H1).
+    pub const fn flip_horizontal(self) -> Square {
+        Square(self.0 ^ 7)
+    }
 
-        (self)/8
+    /// Returns how many king moves it takes to go from
+    /// self to other (Chebyshev distance).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::square::*;
+    /// assert_eq!(A1.distance(C2), 2);
+    /// assert_eq!(A1.manhattan_distance(C2), 3);
+    /// ```
+    pub fn distance(self, other: Square) -> u8 {
+        self.file().abs_diff(other.file()).max(self.rank().abs_diff(other.rank()))
     }
 
-    fn file_as_char(&self) -> char {
-        match self.file() {
-            0 => 'A',
-            1 => 'B',
-            2 => 'C',
-            3 => 'D',
-            4 => 'E',
-            5 => 'F',
-            6 => 'G',
-            7 => 'H',
-            _ => ' ',
-        }
+    /// Returns how many rook steps (one square at a
+    /// time) it takes to go from self to other.
+    pub fn manhattan_distance(self, other: Square) -> u8 {
+        self.file().abs_diff(other.file()) + self.rank().abs_diff(other.rank())
     }
 
-    fn get(&self, dir: &Dir) -> Option<Square> {
-        let i: i8 = *self as i8;
+    /// Returns Some(Square) if a square in dir: dir of
+    /// self is not out of board, if it's out of board
+    /// returns None.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use bitboard::dir::Dir::*;
+    /// # use bitboard::square::*;
+    /// assert_eq!(A2.get(&Left), None);
+    /// assert_eq!(A2.get(&Up), Some(A3));
+    /// ```
+    pub fn get(&self, dir: &Dir) -> Option<Square> {
+        let i: i8 = self.0 as i8;
         let target: i8 = match dir {
             Up => i + 8,
             Down => i - 8,
@@ -183,23 +247,49 @@ impl Squarable for Square {
             HorseLeftUp => i + 6,
         };
 
-        if is_possible(i, &dir) {
-            Some(target as u8)
+        if is_possible(*self, dir) {
+            Some(Square(target as u8))
         } else {
             None
         }
     }
 }
 
-mod move_possible {
-    use super::super::{square::*, dir::{Dir, Dir::*}};
+impl TryFrom<u8> for Square {
+    type Error = SquareError;
+
+    fn try_from(index: u8) -> Result<Square, SquareError> {
+        if index < 64 {
+            Ok(Square(index))
+        } else {
+            Err(SquareError(index.to_string()))
+        }
+    }
+}
+
+/// Parses lowercase algebraic names, like "e4".
+impl FromStr for Square {
+    type Err = SquareError;
 
-    pub fn is_possible(index: i8, dir: &Dir) -> bool {
-        if index < 0 || index > 63 {
-            return false
+    fn from_str(s: &str) -> Result<Square, SquareError> {
+        match s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Ok(Square((rank - b'1') * 8 + (file - b'a'))),
+            _ => Err(SquareError(format!("{:?}", s))),
         }
-        
-        let square: Square = index as Square;
+    }
+}
+
+/// Prints lowercase algebraic name of the square.
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+mod move_possible {
+    use super::super::{square::*, dir::Dir};
+
+    pub fn is_possible(square: Square, dir: &Dir) -> bool {
         let Coordination(file, rank) = square.coord();
 
         match dir {
@@ -213,7 +303,7 @@ mod move_possible {
             DownRight => file != 7 && rank != 0,
             HorseUpLeft => rank < 6 && file != 0,
             HorseUpRight => rank < 6 && file != 7,
-            HorseRightUp => file < 6 && file != 7,
+            HorseRightUp => file < 6 && rank != 7,
             HorseRightDown => file < 6 && rank != 0,
             HorseDownRight => file != 7 && rank > 1,
             HorseDownLeft => file != 0 && rank > 1,
@@ -250,6 +340,47 @@ mod tests {
         assert_eq!(F8.rank(), 7);
     }
 
+    #[test]
+    fn algebraic() {
+        assert_eq!("a1".parse(), Ok(A1));
+        assert_eq!("e4".parse(), Ok(E4));
+        assert_eq!("h8".parse(), Ok(H8));
+        assert!("E4".parse::<Square>().is_err());
+        assert!("e9".parse::<Square>().is_err());
+        assert!("e44".parse::<Square>().is_err());
+        assert!("".parse::<Square>().is_err());
+
+        assert_eq!(A1.to_string(), "a1");
+        assert_eq!(G6.to_string(), "g6");
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Square::try_from(28), Ok(E4));
+        assert!(Square::try_from(64).is_err());
+        assert_eq!(Square::from_file_rank(7, 7), Some(H8));
+        assert_eq!(Square::from_file_rank(0, 8), None);
+        assert_eq!(E4.index(), 28);
+
+        let all: Vec<Square> = Square::all().collect();
+        assert_eq!(all.len(), 64);
+        assert_eq!((all[0], all[63]), (A1, H8));
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn flips_and_distances() {
+        assert_eq!(B2.flip_vertical(), B7);
+        assert_eq!(B2.flip_horizontal(), G2);
+        assert_eq!(E4.flip_vertical().flip_vertical(), E4);
+
+        assert_eq!(A1.distance(H8), 7);
+        assert_eq!(A1.manhattan_distance(H8), 14);
+        assert_eq!(E4.distance(F6), 2);
+        assert_eq!(E4.manhattan_distance(F6), 3);
+        assert_eq!(D5.distance(D5), 0);
+    }
+
     #[test]
     fn get() {
         assert_eq!(A2.get(&Dir::Left), None);
@@ -265,6 +396,7 @@ mod tests {
 
         assert_eq!(G1.get(&Dir::HorseRightUp), None);
         assert_eq!(A2.get(&Dir::HorseRightUp), Some(C3));
+        assert_eq!(B8.get(&Dir::HorseRightUp), None);
 
         assert_eq!(G1.get(&Dir::HorseRightDown), None);
         assert_eq!(F8.get(&Dir::HorseRightDown), Some(H7));
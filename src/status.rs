@@ -0,0 +1,199 @@
+use super::color_side::*;
+use super::game::Game;
+use super::geometry::*;
+use super::piece::*;
+
+/// Whether a game goes on, and if not, why. Threefold
+/// repetition and the fifty move rule only let a player
+/// claim a draw, the others end the game by themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate(ColorSide),             // the winner.
+    Stalemate,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    InsufficientMaterial,
+}
+
+impl Game {
+    /// Returns the status of the game. When more than one
+    /// rule applies, the game ending ones come first:
+    /// checkmate, stalemate, insufficient material, fivefold
+    /// repetition and the seventy-five move rule, then the
+    /// claimable threefold repetition and fifty move rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bitboard::color_side::*;
+    /// # use bitboard::game::Game;
+    /// # use bitboard::status::GameStatus;
+    /// # fn main() -> Result<(), bitboard::fen::FenError> {
+    /// let game = Game::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1")?;
+    ///
+    /// assert_eq!(game.status(), GameStatus::Checkmate(WHITE));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn status(&self) -> GameStatus {
+        if self.legal_moves().is_empty() {
+            return if self.position.is_in_check() {
+                GameStatus::Checkmate(self.position.side_to_move().opposite())
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        let repetitions = self.repetitions();
+
+        if self.is_insufficient_material() {
+            GameStatus::InsufficientMaterial
+        } else if repetitions >= 5 {
+            GameStatus::FivefoldRepetition
+        } else if self.halfmove_clock >= 150 {
+            GameStatus::SeventyFiveMoveRule
+        } else if repetitions >= 3 {
+            GameStatus::ThreefoldRepetition
+        } else if self.halfmove_clock >= 100 {
+            GameStatus::FiftyMoveRule
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /// Returns how many times the current position has
+    /// occurred in the game, counting itself. Positions
+    /// are compared by hash, and only those since the last
+    /// capture or pawn move with the same side to move can
+    /// be the same.
+    pub fn repetitions(&self) -> usize {
+        let hash = self.position.hash();
+        let reversible = self.halfmove_clock.min(self.history.len());
+
+        1 + self.history
+            .iter()
+            .rev()
+            .take(reversible)
+            .skip(1)
+            .step_by(2)
+            .filter(|undo| undo.hash == hash)
+            .count()
+    }
+
+    /// Returns true if neither side can possibly checkmate:
+    /// no pawns, rooks or queens, and either at most one
+    /// knight or bishop, or only bishops, all on squares of
+    /// the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let position = &self.position;
+        if !(position[PAWN] | position[ROOK] | position[QUEEN]).is_empty() {
+            return false;
+        }
+
+        let minors = position[KNIGHT] | position[BISHOP];
+        let bishops = position[BISHOP];
+
+        minors.count() <= 1
+            || (position[KNIGHT].is_empty()
+                && ((bishops & DARK_SQUARES).is_empty() || (bishops & LIGHT_SQUARES).is_empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(fen: &str) -> GameStatus {
+        Game::from_fen(fen).unwrap().status()
+    }
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            let m = game.find_move(uci.parse().unwrap()).unwrap();
+            game.make_move(m);
+        }
+    }
+
+    #[test]
+    fn mate_and_stalemate() {
+        assert_eq!(Game::default().status(), GameStatus::Ongoing);
+        assert_eq!(status("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), GameStatus::Checkmate(WHITE));
+        assert_eq!(status("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), GameStatus::Stalemate);
+
+        let mut game = Game::default();
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(game.status(), GameStatus::Checkmate(BLACK));
+
+        // A mate which reaches the seventy-five move limit
+        // is still a mate.
+        assert_eq!(status("7k/6Q1/6K1/8/8/8/8/8 b - - 150 90"), GameStatus::Checkmate(WHITE));
+    }
+
+    #[test]
+    fn move_rules() {
+        assert_eq!(status("4k3/8/8/8/8/8/8/R3K3 w - - 99 80"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/R3K3 w - - 100 80"), GameStatus::FiftyMoveRule);
+        assert_eq!(status("4k3/8/8/8/8/8/8/R3K3 w - - 150 80"), GameStatus::SeventyFiveMoveRule);
+    }
+
+    #[test]
+    fn repetitions() {
+        let mut game = Game::default();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::ThreefoldRepetition);
+
+        play(&mut game, &shuffle[..2]);
+        assert_eq!(game.repetitions(), 3);
+
+        play(&mut game, &shuffle[2..]);
+        play(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::FivefoldRepetition);
+
+        // A pawn move makes earlier positions unreachable.
+        play(&mut game, &["e2e3", "e7e6"]);
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetitions(), 2);
+
+        // An en passant square no pawn can capture on doesn't
+        // make a position different.
+        let mut game = Game::default();
+        play(&mut game, &["e2e4"]);
+        play(&mut game, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+        play(&mut game, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.status(), GameStatus::ThreefoldRepetition);
+
+        // Same pieces but different castling rights is not
+        // a repetition.
+        let mut game = Game::default();
+        play(&mut game, &["e2e4", "e7e5", "e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert_eq!(game.repetitions(), 1);
+        play(&mut game, &["e1e2", "e8e7", "e2e1", "e7e8"]);
+        assert_eq!(game.repetitions(), 2);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        assert_eq!(status("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4k3/8/8/8/8/8/8/2N1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+        assert_eq!(status("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+
+        // Bishops all on dark squares, of either side.
+        assert_eq!(status("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+        assert_eq!(status("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+
+        assert_eq!(status("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"), GameStatus::Ongoing);
+        assert_eq!(status("4kn2/8/8/8/8/8/8/2B1K3 w - - 0 1"), GameStatus::Ongoing);
+        assert_eq!(status("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"), GameStatus::Ongoing);
+    }
+}
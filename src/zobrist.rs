@@ -0,0 +1,135 @@
+use super::castling::*;
+use super::piece::*;
+use super::rng::Rng;
+use super::square::*;
+
+/// Random keys which are XORed together to get the hash
+/// of a position: one for each piece on each square, one
+/// for black to move, one for each combination of
+/// castling rights and one for each en passant file.
+pub struct ZobristKeys {
+    pub pieces: [[u64; 64]; 12],
+    pub black_to_move: u64,
+    pub castling: [u64; 16],
+    pub en_passant: [u64; 8],
+}
+
+/// Keys are generated at compile time from a fixed seed,
+/// so hashes are the same across runs.
+pub static KEYS: ZobristKeys = generate_keys(0x5A0B_2157);
+
+pub fn piece_square(piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[piece.index()][square.index()]
+}
+
+pub fn black_to_move() -> u64 {
+    KEYS.black_to_move
+}
+
+pub fn castling(rights: CastlingRights) -> u64 {
+    KEYS.castling[rights as usize]
+}
+
+/// Only the file of the en passant square is hashed,
+/// the rank follows from the side to move.
+pub fn en_passant(square: Option<Square>) -> u64 {
+    match square {
+        Some(square) => KEYS.en_passant[square.file() as usize],
+        None => 0,
+    }
+}
+
+const fn generate_keys(seed: u64) -> ZobristKeys {
+    let mut rng = Rng::new(seed);
+    let mut keys = ZobristKeys {
+        pieces: [[0; 64]; 12],
+        black_to_move: 0,
+        castling: [0; 16],
+        en_passant: [0; 8],
+    };
+
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            keys.pieces[piece][square] = rng.next_u64();
+            square += 1;
+        }
+        piece += 1;
+    }
+
+    keys.black_to_move = rng.next_u64();
+
+    // No castling rights hash to zero, like an empty board.
+    let mut rights = 1;
+    while rights < 16 {
+        keys.castling[rights] = rng.next_u64();
+        rights += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant[file] = rng.next_u64();
+        file += 1;
+    }
+
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+    use crate::rng::Rng;
+
+    #[test]
+    fn incremental_matches_from_scratch() {
+        let mut rng = Rng::new(0x2B1A_5E01);
+
+        for _ in 0..50 {
+            let mut game = Game::default();
+            let start = game.position.hash();
+
+            for _ in 0..80 {
+                let moves = game.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+
+                game.make_move(moves[(rng.next_u64() % moves.len() as u64) as usize]);
+                assert_eq!(game.position.hash(), game.position.compute_hash(), "after {}", game.to_fen());
+            }
+
+            while game.unmake_move().is_some() {
+                assert_eq!(game.position.hash(), game.position.compute_hash());
+            }
+            assert_eq!(game.position.hash(), start);
+        }
+    }
+
+    #[test]
+    fn transpositions() {
+        let play = |moves: &[&str]| {
+            let mut game = Game::default();
+            for uci in moves {
+                let m = game.find_move(uci.parse().unwrap()).unwrap();
+                game.make_move(m);
+            }
+            game.position.hash()
+        };
+
+        assert_eq!(play(&["g1f3", "g8f6", "b1c3"]), play(&["b1c3", "g8f6", "g1f3"]));
+        assert_eq!(play(&["g1f3", "g8f6", "f3g1", "f6g8"]), play(&[]));
+        assert_ne!(play(&["g1f3", "g8f6"]), play(&["g1f3"]));
+
+        let hash = |fen: &str| Game::from_fen(fen).unwrap().position.hash();
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_ne!(hash(fen), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Qkq e3 0 1"));
+
+        // The en passant square only counts if it can be
+        // captured on, by the d4 pawn here.
+        assert_eq!(hash(fen), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_ne!(hash(fen), hash("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+        assert_ne!(hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1"), hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+    }
+}
\ No newline at end of file